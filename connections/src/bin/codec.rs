@@ -0,0 +1,332 @@
+use nyt_connections::dictionary::MAX_DICTIONARY_LEN;
+use nyt_connections::{
+    envelope, limits, signing, CodeVersion, ConnectionPuzzle, ConnectionSet, Keyring,
+    PuzzleArchive, PuzzlePack, TranscodingError,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+usage: codec <command> <argument>...
+
+commands:
+  encode <file> [pass]     turn a puzzle file (JSON, or - for stdin) into a puzzle code,
+                           locked behind a passphrase if one is given
+  seal <file>              turn a puzzle file into a code that hides its groups until guessed
+  pack <file>              turn a pack file, a title and a list of puzzles, into one code
+  decode <code> [pass]     print the puzzle or pack behind a code as JSON
+  inspect <code> [pass]    show every stage of decoding a code
+  keygen                   make a key for signing codes, and print it and its public key
+  sign <code> <key file>   sign a code with the secret key in a file
+  verify <code> <name>=<public key>...
+                           say which of the named keys signed a code, if any
+  train <out> <corpus>...  build a compression dictionary from word lists and archives
+  bench <archive>          compare code lengths with the dictionary against plain gzip";
+
+#[derive(Deserialize)]
+struct PuzzleGroups {
+    sets: Vec<ConnectionSet>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [command, file, passphrase @ ..] if command == "encode" && passphrase.len() <= 1 => {
+            encode(file, passphrase.first())
+        }
+        [command, file] if command == "seal" => seal(file),
+        [command, file] if command == "pack" => pack(file),
+        [command, code, passphrase @ ..] if command == "decode" && passphrase.len() <= 1 => {
+            decode(code, passphrase.first())
+        }
+        [command, code, passphrase @ ..] if command == "inspect" && passphrase.len() <= 1 => {
+            inspect(code, passphrase.first())
+        }
+        [command] if command == "keygen" => keygen(),
+        [command, code, key] if command == "sign" => sign(code, key),
+        [command, code, keys @ ..] if command == "verify" && !keys.is_empty() => verify(code, keys),
+        [command, out, corpus @ ..] if command == "train" && !corpus.is_empty() => {
+            train(out, corpus)
+        }
+        [command, archive] if command == "bench" => bench(archive),
+        _ => Err(USAGE.into()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read(file: &str) -> Result<String, String> {
+    let mut text = String::new();
+    if file == "-" {
+        std::io::stdin().read_to_string(&mut text)
+    } else {
+        std::fs::File::open(file).and_then(|mut file| file.read_to_string(&mut text))
+    }
+    .map_err(|error| format!("couldn't read {file}: {error}"))?;
+    Ok(text)
+}
+
+fn read_archive(file: &str) -> Result<PuzzleArchive, String> {
+    serde_json::from_str(&read(file)?).map_err(|error| format!("invalid archive {file}: {error}"))
+}
+
+fn encode(file: &str, passphrase: Option<&String>) -> Result<(), String> {
+    let puzzle = read_puzzle(file)?;
+    let code = match passphrase {
+        Some(passphrase) => puzzle.try_encode_locked(passphrase),
+        None => puzzle.try_encode(),
+    };
+    println!("{}", code.map_err(|error| too_big(&error))?);
+    Ok(())
+}
+
+fn seal(file: &str) -> Result<(), String> {
+    let code = read_puzzle(file)?.try_encode_sealed();
+    println!("{}", code.map_err(|error| too_big(&error))?);
+    Ok(())
+}
+
+fn pack(file: &str) -> Result<(), String> {
+    let pack: PuzzlePack = serde_json::from_str(&read(file)?)
+        .map_err(|error| format!("invalid pack file: {error}"))?;
+    println!("{}", pack.try_encode().map_err(|error| too_big(&error))?);
+    Ok(())
+}
+
+fn read_puzzle(file: &str) -> Result<ConnectionPuzzle, String> {
+    let json = read(file)?;
+
+    // the groups are checked on their own first, so every problem with them is listed and
+    // not just the first one that stops the whole puzzle from parsing
+    let groups: PuzzleGroups =
+        serde_json::from_str(&json).map_err(|error| format!("invalid puzzle file: {error}"))?;
+    if let Err(error) = ConnectionPuzzle::validated(groups.sets) {
+        let problems: Vec<String> = error
+            .problems_ref()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        return Err(format!("invalid puzzle:\n  {}", problems.join("\n  ")));
+    }
+    serde_json::from_str(&json).map_err(|error| format!("invalid puzzle file: {error}"))
+}
+
+fn decode(code: &str, passphrase: Option<&String>) -> Result<(), String> {
+    if PuzzlePack::is_pack(code.trim()) {
+        let pack = PuzzlePack::decode(code.trim()).map_err(|error| stage_failed(&error))?;
+        let json = serde_json::to_string_pretty(&pack).expect("packs are always valid JSON");
+        println!("{json}");
+        return Ok(());
+    }
+    let puzzle = ConnectionPuzzle::decode_with(code.trim(), passphrase.map(String::as_str))
+        .map_err(|error| stage_failed(&error))?;
+    let json = serde_json::to_string_pretty(&puzzle).expect("puzzles are always valid JSON");
+    println!("{json}");
+    Ok(())
+}
+
+fn inspect(code: &str, passphrase: Option<&String>) -> Result<(), String> {
+    let code = code.trim();
+    if code == "debug" || code == "default" {
+        println!("{code} is an alias for a built-in puzzle");
+        return decode(code, None);
+    }
+    let (code, signed) = (signing::unsigned(code), code.contains(signing::SEPARATOR));
+    println!("base64:     {} characters", code.len());
+    if signed {
+        println!("signed:     yes, see verify");
+    }
+
+    let bytes = ConnectionPuzzle::unwrap_base64(code).map_err(|e| stage_failed(&e))?;
+    if code.contains('~') {
+        println!("checksum:   ok");
+    } else {
+        println!("checksum:   none, made before codes had one");
+    }
+    let (version, body) = envelope::open(&bytes).map_err(|e| stage_failed(&e))?;
+    match version.number() {
+        Some(number) => println!("version:    {number}"),
+        None => println!("version:    none, made before codes were versioned"),
+    }
+    let unlocked_bytes;
+    let compressed_bytes = if version == CodeVersion::V3 {
+        println!("locked:     {} bytes", body.len());
+        unlocked_bytes = ConnectionPuzzle::unlock(passphrase.map(String::as_str), body)
+            .map_err(|e| stage_failed(&e))?;
+        &unlocked_bytes
+    } else {
+        body
+    };
+    println!("compressed: {} bytes", compressed_bytes.len());
+
+    let postcard_bytes =
+        ConnectionPuzzle::decompress(version, compressed_bytes).map_err(|e| stage_failed(&e))?;
+    println!("postcard:   {} bytes", postcard_bytes.len());
+
+    let json = if version == CodeVersion::V5 {
+        let pack = PuzzlePack::deserialize(&postcard_bytes).map_err(|e| stage_failed(&e))?;
+        println!("puzzles:    {}", pack.len());
+        serde_json::to_string_pretty(&pack).expect("packs are always valid JSON")
+    } else {
+        let puzzle = ConnectionPuzzle::deserialize_version(version, &postcard_bytes)
+            .map_err(|e| stage_failed(&e))?;
+        serde_json::to_string_pretty(&puzzle).expect("puzzles are always valid JSON")
+    };
+    println!("{json}");
+    Ok(())
+}
+
+fn keygen() -> Result<(), String> {
+    let secret_key = signing::generate_key();
+    let public_key = signing::public_key(&secret_key).expect("new keys are valid");
+    println!("secret key: {secret_key}");
+    println!("public key: {public_key}");
+    Ok(())
+}
+
+fn sign(code: &str, key_file: &str) -> Result<(), String> {
+    let code = code.trim();
+    // signing something that isn't a code would only make a signed dud
+    ConnectionPuzzle::unwrap_base64(signing::unsigned(code)).map_err(|e| stage_failed(&e))?;
+    let signed = signing::sign(code, &read(key_file)?).map_err(|error| error.to_string())?;
+    limits::code(&signed).map_err(|error| too_big(&error))?;
+    println!("{signed}");
+    Ok(())
+}
+
+fn verify(code: &str, keys: &[String]) -> Result<(), String> {
+    let keyring = read_keyring(keys)?;
+    match keyring.signer(code.trim()).map_err(|e| stage_failed(&e))? {
+        Some(name) => println!("signed by {name}"),
+        None => println!("not signed by any of those keys"),
+    }
+    Ok(())
+}
+
+/// Reads `name=public key` pairs
+fn read_keyring(keys: &[String]) -> Result<Keyring, String> {
+    keys.iter().try_fold(Keyring::new(), |keyring, pair| {
+        let (name, key) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("{pair} isn't name=public key"))?;
+        keyring
+            .trust(name, key)
+            .map_err(|error| format!("{name}: {error}"))
+    })
+}
+
+/// For a puzzle that's fine in itself but over the limits codes are decoded under
+fn too_big(error: &TranscodingError) -> String {
+    format!("the puzzle is too big to make a code that opens: {error}")
+}
+
+fn stage_failed(error: &TranscodingError) -> String {
+    let stage = match error {
+        TranscodingError::NotACode => "base64",
+        TranscodingError::Truncated { .. }
+        | TranscodingError::Typo { .. }
+        | TranscodingError::Missing { .. }
+        | TranscodingError::Extra { .. }
+        | TranscodingError::Mistyped => "checksum",
+        TranscodingError::Decompress => "decompression",
+        TranscodingError::Postcard | TranscodingError::EmptyPack => "postcard",
+        TranscodingError::UnsupportedVersion(_) | TranscodingError::Pack => "version",
+        TranscodingError::Passphrase | TranscodingError::Sealed | TranscodingError::Locked => {
+            "unlock"
+        }
+        TranscodingError::Signature => "signature",
+        TranscodingError::CodeTooLong { .. } => "base64",
+        TranscodingError::TooLarge { .. } => "decompression",
+        TranscodingError::TooLong { .. } | TranscodingError::TooMany { .. } => "limits",
+    };
+    format!("{stage} stage failed: {error}")
+}
+
+/// Counts every line of the word lists and every theme, word and hint of the archives (any
+/// `.json` file), and keeps what saves the most bytes. Each entry is written the way postcard
+/// writes a string, so a match takes in the length in front of it too.
+fn train(out: &str, corpus: &[String]) -> Result<(), String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut count = |text: &str| *counts.entry(text.to_string()).or_default() += 1;
+    for file in corpus {
+        if file.ends_with(".json") {
+            for daily in read_archive(file)?.iter() {
+                for set in daily.puzzle.sets() {
+                    count(set.theme_ref());
+                    set.words_list_ref().for_each(&mut count);
+                    set.hints().iter().for_each(|hint| count(hint));
+                }
+            }
+        } else {
+            read(file)?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .for_each(&mut count);
+        }
+    }
+
+    let mut entries: Vec<(String, usize)> = counts.into_iter().collect();
+    let saving = |(text, count): &(String, usize)| count * (text.len() + 1);
+    entries.sort_by(|a, b| saving(b).cmp(&saving(a)).then_with(|| a.0.cmp(&b.0)));
+    let mut chosen = Vec::new();
+    let mut len = 0;
+    for (text, _) in &entries {
+        let entry = postcard::to_allocvec(text).expect("strings always serialize");
+        if len + entry.len() <= MAX_DICTIONARY_LEN {
+            len += entry.len();
+            chosen.push(entry);
+        }
+    }
+    // deflate spends fewer bits on matches close by, so the best entries go last, right
+    // before the puzzle
+    chosen.reverse();
+    let dictionary = chosen.concat();
+    std::fs::write(out, &dictionary).map_err(|error| format!("couldn't write {out}: {error}"))?;
+    println!("{} entries, {} bytes", chosen.len(), dictionary.len());
+    Ok(())
+}
+
+/// Code lengths in characters for every puzzle in an archive
+fn bench(file: &str) -> Result<(), String> {
+    let archive = read_archive(file)?;
+    println!(
+        "{:>5}  {:>6}  {:>10}  {:>6}",
+        "#", "gzip", "dictionary", "saved"
+    );
+    let (mut gzip_total, mut dictionary_total) = (0, 0);
+    for daily in archive.iter() {
+        let gzip = ConnectionPuzzle::gzip(&daily.puzzle.serialize());
+        let gzip = ConnectionPuzzle::wrap_base64(&envelope::seal(1, &gzip)).len();
+        let code = daily.puzzle.encode();
+        let decoded = ConnectionPuzzle::decode(&code).map_err(|e| stage_failed(&e))?;
+        if decoded.serialize() != daily.puzzle.serialize() {
+            return Err(format!("puzzle #{} didn't survive encoding", daily.number));
+        }
+        let dictionary = code.len();
+        println!(
+            "{:>5}  {gzip:>6}  {dictionary:>10}  {:>5.1}%",
+            daily.number,
+            saved(gzip, dictionary)
+        );
+        gzip_total += gzip;
+        dictionary_total += dictionary;
+    }
+    println!(
+        "{:>5}  {gzip_total:>6}  {dictionary_total:>10}  {:>5.1}%",
+        "total",
+        saved(gzip_total, dictionary_total)
+    );
+    Ok(())
+}
+
+fn saved(before: usize, after: usize) -> f64 {
+    100.0 * (before as f64 - after as f64) / before as f64
+}
@@ -0,0 +1,447 @@
+use chrono::{Local, NaiveDate};
+use nyt_connections::{
+    seed_from_text, CardState, ConnectionPuzzle, Failiure, GameState, Keyring, PuzzleArchive,
+    PuzzlePack, SelectionSuccess, Trap, MAX_SCORE,
+};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+const HELP: &str = "\
+Select cards by number or by word, several at a time separated by spaces or commas.
+Commands:
+  submit, !     check the current selection
+  shuffle, s    shuffle the remaining cards
+  deselect, d   clear the current selection
+  undo, u       take back the last selection, shuffle or (with --practice) guess
+  redo, r       put back what was undone
+  save          print a code that picks this game back up with --resume=<code>
+  seed          print the seed that replays this game's shuffles with --seed=<seed>
+  hint [group]  a hint about a group, by label or color, the easiest unsolved one if none is given
+  word [group]  give away one word of a group
+  solve [group] give away a whole group
+  log [json]    print every guess so far as CSV, or as JSON
+  help, ?       show this message
+  quit, q       give up and exit";
+
+fn main() -> ExitCode {
+    let (flags, codes): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let archive = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--archive="));
+    let puzzle = match archive {
+        Some(path) => daily_puzzle(path, &flags),
+        None => {
+            let code = codes.first().map_or("default", String::as_str);
+            let passphrase = flags
+                .iter()
+                .find_map(|flag| flag.strip_prefix("--passphrase="));
+            trusted_keys(&flags).and_then(|keyring| {
+                if PuzzlePack::is_pack(code) {
+                    return pack_puzzle(code, &flags, &keyring);
+                }
+                ConnectionPuzzle::decode_trusted_with(code, passphrase, &keyring)
+                    .map(|puzzle| Some((None, puzzle)))
+                    .map_err(|error| format!("invalid puzzle code: {error}"))
+            })
+        }
+    };
+    let (number, puzzle) = match puzzle {
+        Ok(Some(picked)) => picked,
+        Ok(None) => return ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let rules = if flags.iter().any(|flag| flag == "--practice") {
+        Some(puzzle.rules().with_practice(true))
+    } else {
+        None
+    };
+    // anything that isn't a number, like a date, is hashed into one
+    let seed = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--seed="))
+        .map(|seed| seed.parse().unwrap_or_else(|_| seed_from_text(seed)));
+    let resume = flags.iter().find_map(|flag| flag.strip_prefix("--resume="));
+    let mut game = match resume {
+        Some(save) => match GameState::resume_code(puzzle, save) {
+            Ok(game) => game,
+            Err(error) => {
+                eprintln!("can't resume: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => match seed {
+            Some(seed) => GameState::seeded(puzzle, rules, seed),
+            None => GameState::new(puzzle, rules),
+        },
+    };
+
+    println!("{HELP}");
+    introduce(&game);
+    render(&game);
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => return ExitCode::SUCCESS,
+            Ok(_) => {}
+        }
+
+        match line.trim().to_lowercase().as_str() {
+            "" => render(&game),
+            "help" | "?" => println!("{HELP}"),
+            "quit" | "q" => {
+                reveal(&game);
+                game_over(&game);
+                return ExitCode::SUCCESS;
+            }
+            "shuffle" | "s" => {
+                game.shuffle();
+                render(&game);
+            }
+            "deselect" | "d" => {
+                game.clear_selection();
+                render(&game);
+            }
+            "save" => println!("--resume={}", game.save_code()),
+            "seed" => println!("--seed={}", game.seed()),
+            "log" => print!("{}", game.log_csv()),
+            "log json" => println!("{}", game.log_json()),
+            "undo" | "u" => {
+                if !game.undo() {
+                    println!("Nothing to undo");
+                }
+                render(&game);
+            }
+            "redo" | "r" => {
+                if !game.redo() {
+                    println!("Nothing to redo");
+                }
+                render(&game);
+            }
+            "submit" | "!" => match submit(&mut game, number) {
+                Some(code) => return code,
+                None => render(&game),
+            },
+            command if HINT_COMMANDS.contains(&command.split_whitespace().next().unwrap_or("")) => {
+                match take_hint(&mut game, command, number) {
+                    Some(code) => return code,
+                    None => render(&game),
+                }
+            }
+            _ => {
+                select(&mut game, line.trim());
+                render(&game);
+            }
+        }
+    }
+}
+
+/// Picks the puzzle for `--date=<YYYY-MM-DD>`, or for today where this is being run, out of an
+/// archive file. `--list` prints the puzzles published by then instead of picking one.
+fn daily_puzzle(
+    path: &str,
+    flags: &[String],
+) -> Result<Option<(Option<u32>, ConnectionPuzzle)>, String> {
+    let json = fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    let archive: PuzzleArchive =
+        serde_json::from_str(&json).map_err(|error| format!("invalid archive: {error}"))?;
+    let date = match flags.iter().find_map(|flag| flag.strip_prefix("--date=")) {
+        Some(date) => date
+            .parse::<NaiveDate>()
+            .map_err(|error| format!("invalid date {date}: {error}"))?,
+        None => Local::now().date_naive(),
+    };
+
+    if flags.iter().any(|flag| flag == "--list") {
+        for daily in archive.published_by(date) {
+            println!("#{:<4} {}", daily.number, daily.date);
+        }
+        return Ok(None);
+    }
+    let daily = archive.on(date).map_err(|error| error.to_string())?;
+    println!("Puzzle #{} for {}", daily.number, daily.date);
+    Ok(Some((Some(daily.number), daily.puzzle.clone())))
+}
+
+/// Picks puzzle `--puzzle=<number>` out of a pack, or the first one. `--list` prints the
+/// pack's puzzles instead of picking one.
+fn pack_puzzle(
+    code: &str,
+    flags: &[String],
+    keyring: &Keyring,
+) -> Result<Option<(Option<u32>, ConnectionPuzzle)>, String> {
+    let pack = PuzzlePack::decode_trusted(code, keyring)
+        .map_err(|error| format!("invalid pack code: {error}"))?;
+    if flags.iter().any(|flag| flag == "--list") {
+        println!("{}", pack.title());
+        for (index, puzzle) in pack.puzzles().iter().enumerate() {
+            let title = puzzle.metadata().title().unwrap_or_default();
+            println!("#{:<4} {title}", index + 1);
+        }
+        return Ok(None);
+    }
+    let number = match flags.iter().find_map(|flag| flag.strip_prefix("--puzzle=")) {
+        Some(number) => number
+            .parse()
+            .map_err(|error| format!("invalid puzzle number {number}: {error}"))?,
+        None => 1,
+    };
+    let puzzle = pack.number(number).map_err(|error| error.to_string())?;
+    println!("{}: puzzle {number} of {}", pack.title(), pack.len());
+    Ok(Some((Some(number), puzzle.clone())))
+}
+
+/// `number` is the puzzle's number in its archive, for sharing the result
+fn submit(game: &mut GameState, number: Option<u32>) -> Option<ExitCode> {
+    match game.check_selection() {
+        Ok(SelectionSuccess::Matched(color)) => {
+            println!("Matched {}!", game.puzzle().label(color));
+            None
+        }
+        Ok(SelectionSuccess::Won(_)) => {
+            render(game);
+            println!("You Win!");
+            game_over(game);
+            println!("\n{}", game.share_text(number, None));
+            Some(ExitCode::SUCCESS)
+        }
+        Err(Failiure::NotEnough) => {
+            println!("Select {} cards before submitting", game.group_size());
+            None
+        }
+        Err(Failiure::AlreadyTried) => {
+            println!("Already Guessed!");
+            None
+        }
+        Err(Failiure::OneAway) => {
+            println!("One Away...");
+            herrings(game.fell_for());
+            None
+        }
+        Err(Failiure::Mismatch) => {
+            println!("Not quite");
+            herrings(game.fell_for());
+            None
+        }
+        Err(Failiure::GameOver) => Some(ExitCode::SUCCESS),
+        Err(Failiure::Lost) => {
+            println!("Better luck next time!");
+            reveal(game);
+            game_over(game);
+            println!("\n{}", game.share_text(number, None));
+            Some(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn herrings(traps: Vec<Trap>) {
+    for trap in traps {
+        println!("Red herring! {}", trap.message());
+    }
+}
+
+/// Every `--trust=<name>=<public key>`
+fn trusted_keys(flags: &[String]) -> Result<Keyring, String> {
+    flags
+        .iter()
+        .filter_map(|flag| flag.strip_prefix("--trust="))
+        .try_fold(Keyring::new(), |keyring, pair| {
+            let (name, key) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("--trust={pair} isn't --trust=<name>=<public key>"))?;
+            keyring
+                .trust(name, key)
+                .map_err(|error| format!("can't trust {name}: {error}"))
+        })
+}
+
+/// The puzzle's title, author and intro, whichever it has
+fn introduce(game: &GameState) {
+    if let Some(signer) = game.verified_by() {
+        println!("Verified: signed by {signer}");
+    }
+    let metadata = game.puzzle().metadata();
+    match (metadata.title(), metadata.author()) {
+        (Some(title), Some(author)) => println!("{title}, by {author}"),
+        (Some(title), None) => println!("{title}"),
+        (None, Some(author)) => println!("A puzzle by {author}"),
+        (None, None) => {}
+    }
+    if let Some(intro) = metadata.intro() {
+        println!("{intro}");
+    }
+}
+
+/// Every red herring the player fell for, then the puzzle's end note
+fn game_over(game: &GameState) {
+    let traps = game.traps();
+    if !traps.is_empty() {
+        println!("\nRed herrings that got you:");
+        for trap in traps {
+            println!("  {}", trap.message());
+        }
+    }
+    if let Some(note) = game.puzzle().metadata().end_note() {
+        println!("\n{note}");
+    }
+}
+
+const HINT_COMMANDS: [&str; 3] = ["hint", "word", "solve"];
+
+fn take_hint(game: &mut GameState, command: &str, number: Option<u32>) -> Option<ExitCode> {
+    let (kind, name) = command
+        .trim()
+        .split_once(' ')
+        .unwrap_or((command.trim(), ""));
+    let name = Some(name.trim()).filter(|name| !name.is_empty());
+    let color = match name.map(|name| game.puzzle().group_named(name)) {
+        None => None,
+        Some(Some(color)) => Some(color),
+        Some(None) => {
+            println!("There's no such group");
+            return None;
+        }
+    };
+    let hint = match kind {
+        "hint" => game.hint(color),
+        "word" => game.reveal_word(color),
+        _ => {
+            return match game.reveal_group(color) {
+                Ok(SelectionSuccess::Won(_)) => {
+                    render(game);
+                    println!("Solved!");
+                    game_over(game);
+                    println!("\n{}", game.share_text(number, None));
+                    Some(ExitCode::SUCCESS)
+                }
+                Ok(SelectionSuccess::Matched(_)) => None,
+                Err(error) => {
+                    println!("No hint: {error}");
+                    None
+                }
+            };
+        }
+    };
+    match hint {
+        Ok(hint) => println!("{}: {}", game.puzzle().label(hint.color()), hint.text()),
+        Err(error) => println!("No hint: {error}"),
+    }
+    None
+}
+
+fn select(game: &mut GameState, input: &str) {
+    for part in input
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        if let Some(index) = find_word(game, part) {
+            toggle(game, index);
+            continue;
+        }
+        for token in part.split_whitespace() {
+            let index = match token.parse::<usize>() {
+                Ok(number) if (1..=game.num_cards()).contains(&number) => Some(number - 1),
+                Ok(number) => {
+                    println!("There's no card {number}");
+                    None
+                }
+                Err(_) => find_word(game, token).or_else(|| {
+                    println!("There's no card \"{token}\"");
+                    None
+                }),
+            };
+            if let Some(index) = index {
+                toggle(game, index);
+            }
+        }
+    }
+}
+
+fn toggle(game: &mut GameState, index: usize) {
+    if game.get(index).state == CardState::Matched {
+        println!("\"{}\" is already matched", game.get(index).word);
+    } else if game.select(index).is_err() {
+        println!("You can only select {} cards", game.group_size());
+    }
+}
+
+/// finds an unmatched card with the given word, preferring ones that aren't selected yet so
+/// puzzles with repeated words can still be played by word
+fn find_word(game: &GameState, word: &str) -> Option<usize> {
+    let candidates: Vec<usize> = (0..game.num_cards())
+        .filter(|&index| {
+            let card = game.get(index);
+            card.state != CardState::Matched && card.word.eq_ignore_ascii_case(word)
+        })
+        .collect();
+    candidates
+        .iter()
+        .copied()
+        .find(|&index| game.get(index).state == CardState::Normal)
+        .or(candidates.first().copied())
+}
+
+fn render(game: &GameState) {
+    let width = (0..game.num_cards())
+        .map(|index| game.get(index).word.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    println!();
+    let row_len = game.group_size();
+    for row in (0..game.num_cards()).step_by(row_len) {
+        let first = game.get(row);
+        if first.state == CardState::Matched {
+            if let Some(text) = game.matched_text(first.color) {
+                println!("  {:<8}{}: {}", first.label, text[0], text[1]);
+                continue;
+            }
+        }
+        let mut line = String::new();
+        for index in row..row + row_len {
+            let card = game.get(index);
+            let word = match card.state {
+                CardState::Selected => format!("[{}]", card.word.to_uppercase()),
+                _ => format!(" {} ", card.word),
+            };
+            line.push_str(&format!("{:>2}.{word:<width$} ", index + 1));
+        }
+        println!("{}", line.trim_end());
+    }
+    println!();
+    match game.mistakes_remaining() {
+        Some(remaining) => println!(
+            "Mistakes remaining: {}",
+            "* ".repeat(remaining.into()).trim_end()
+        ),
+        None => println!("Mistakes made: {}", game.mistakes()),
+    }
+    if game.score() < MAX_SCORE {
+        println!("Score: {}", game.score());
+    }
+}
+
+fn reveal(game: &GameState) {
+    println!();
+    for color in game.puzzle().colors() {
+        let label = game.puzzle().label(color);
+        if !game.puzzle().is_found(color) {
+            println!("  {label:<8}sealed, and never guessed");
+            continue;
+        }
+        // goes by the solution the game settled on, which may not be the one written out
+        if let Some(text) = game.matched_text(color) {
+            println!("  {label:<8}{}: {}", text[0], text[1]);
+        }
+    }
+}
@@ -1,45 +1,95 @@
-mod board;
+pub mod alternative;
+pub mod archive;
+pub mod board;
+mod checksum;
 pub mod color;
-mod puzzle;
-#[allow(unused_imports)]
-use crate::console_log;
+pub mod dictionary;
+pub mod envelope;
+pub mod herring;
+pub mod hints;
+mod history;
+pub mod limits;
+mod lock;
+pub mod log;
+pub mod metadata;
+pub mod pack;
+pub mod puzzle;
+pub mod rules;
+pub mod save;
+mod seal;
+pub mod signing;
+pub mod validate;
 use board::Board;
+use board::BoardSnapshot;
 use board::Card;
+#[cfg(feature = "wasm")]
 use board::OwnedCard;
 use board::Selection;
 use board::SelectionFailiure;
 use color::Color;
+use herring::Trap;
+use hints::{Hint, HintError, HintKind, UsedHint, MAX_SCORE};
+use history::History;
+use log::{GuessLog, GuessOutcome};
+use metadata::Metadata;
 pub use puzzle::ConnectionPuzzle;
 pub use puzzle::ConnectionSet;
-use puzzle::TranscodingError;
+use puzzle::{PuzzleRef, TranscodingError};
+pub use rules::Rules;
+use save::{SaveError, SaveState};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 #[derive(Debug)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct GameState {
     mistakes: u8,
     successes: u8,
     board: Board,
-    prev_attempts: Vec<Selection>,
+    /// Every guess that counted, matched or not, in the order they were made
+    guesses: Vec<Selection>,
+    hints: Vec<UsedHint>,
+    rules: Rules,
+    history: History<Snapshot>,
+    log: GuessLog,
 }
 
-#[wasm_bindgen]
+/// Everything undo and redo bring back, and everything a save holds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Snapshot {
+    mistakes: u8,
+    successes: u8,
+    guesses: Vec<Selection>,
+    hints: Vec<UsedHint>,
+    board: BoardSnapshot,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl GameState {
+    /// See [`ConnectionPuzzle::share_code`]
     pub fn puzzle_code(&self) -> String {
-        self.board.encode()
+        self.board.puzzle().share_code()
+    }
+
+    /// Whether the puzzle came from a locked code, which there's no editing without the
+    /// passphrase
+    pub fn is_locked(&self) -> bool {
+        self.board.puzzle().came_locked()
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Self {
         let puzzle = ConnectionPuzzle::default();
-        Self::new(puzzle)
+        Self::new(puzzle, None)
     }
 
     pub fn select(&mut self, card_id: usize) -> Result<usize, SelectionFailiure> {
-        self.board.select(card_id)
+        self.undoable(|game| game.board.select(card_id))
     }
 
     fn record_mistake(&mut self) {
-        self.mistakes += 1;
+        self.mistakes = self.mistakes.saturating_add(1);
         //self.dom.deactivate_dot();
     }
 
@@ -47,58 +97,51 @@ impl GameState {
         self.board.get_word(index).to_string()
     }
 
-    fn swap_on_success(&mut self) {}
+    pub fn num_cards(&self) -> usize {
+        self.board.num_cards()
+    }
 
-    pub fn check_selection(&mut self) -> Result<JsSelectionSuccess, Failiure> {
-        use Failiure::*;
-        use SelectionSuccess::{Matched, Won};
+    pub fn num_groups(&self) -> usize {
+        self.board.num_groups()
+    }
 
-        if self.prev_attempts.contains(&self.board.selection) {
-            return Err(AlreadyTried);
-        }
+    pub fn group_size(&self) -> usize {
+        self.board.group_size()
+    }
 
-        let almost_won = self.successes == 3;
+    pub fn mistakes(&self) -> u8 {
+        self.mistakes
+    }
 
-        let almost_lost = self.mistakes == 3;
+    /// `None` when the rules don't limit mistakes
+    pub fn mistakes_remaining(&self) -> Option<u8> {
+        let limit = self.rules.mistake_limit()?;
+        Some(limit.saturating_sub(self.mistakes))
+    }
 
-        match self.board.test_selection() {
-            Ok(color) => {
-                self.successes += 1;
-                self.swap_on_success();
-                if almost_won {
-                    Ok(Won(color).into())
-                } else {
-                    Ok(Matched(color).into())
-                }
-            }
-            Err(SelectionFailiure::Mismatch) => {
-                self.prev_attempts.push(self.board.selection.clone());
-                self.record_mistake();
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
 
-                if almost_lost {
-                    Err(Lost)
-                } else {
-                    Err(Mismatch)
-                }
-            }
-            Err(SelectionFailiure::OneAway) => {
-                self.prev_attempts.push(self.board.selection.clone());
-                self.record_mistake();
-                if almost_lost {
-                    Err(Lost)
-                } else {
-                    Err(OneAway)
-                }
-            }
-            Err(SelectionFailiure::NotEnough) => Err(NotEnough),
-        }
+    pub fn seed(&self) -> u64 {
+        self.board.seed()
+    }
+
+    fn swap_on_success(&mut self) {}
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = check_selection)]
+    pub fn js_check_selection(&mut self) -> Result<JsSelectionSuccess, Failiure> {
+        self.check_selection()
+            .map(|success| self.js_success(success))
     }
+
     pub fn shuffle(&mut self) {
-        self.board.shuffle();
+        self.undoable(|game| game.board.shuffle());
     }
 
     pub fn clear_selection(&mut self) {
-        self.board.deselect_all();
+        self.undoable(|game| game.board.deselect_all());
         //self.render_cards();
         /*
         self.dom.clear_selections();
@@ -126,50 +169,300 @@ impl GameState {
 
     pub fn from_code(code: &str) -> Result<Self, TranscodingError> {
         let puzzle = ConnectionPuzzle::decode(code)?;
-        Ok(Self::new(puzzle))
+        Ok(Self::new(puzzle, None))
     }
 
     pub fn start_over(&mut self) {
         self.mistakes = 0;
         self.successes = 0;
         self.board.reset();
-        self.prev_attempts.clear();
+        self.guesses.clear();
+        self.hints.clear();
+        self.history.clear();
+        self.log.clear();
         //self.dom.reset();
     }
 
-    pub fn matched_text(&self, color: &str) -> Box<[String]> {
-        let Ok(color) = color.try_into() else {
-            panic!("Error, {color} is not a valid color string");
+    /// Returns whether there was anything to undo
+    pub fn undo(&mut self) -> bool {
+        let current = self.snapshot();
+        let Some(previous) = self.history.undo(current) else {
+            return false;
         };
+        self.restore(previous);
+        true
+    }
+
+    /// Returns whether there was anything to redo
+    pub fn redo(&mut self) -> bool {
+        let current = self.snapshot();
+        let Some(next) = self.history.redo(current) else {
+            return false;
+        };
+        self.restore(next);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn save_code(&self) -> String {
+        self.save().encode()
+    }
+
+    pub fn resume_code(puzzle: ConnectionPuzzle, code: &str) -> Result<Self, SaveError> {
+        let save = SaveState::decode(code)?;
+        Self::resume(puzzle, save)
+    }
+
+    /// The theme and words of the group, or `None` if the puzzle doesn't have it
+    pub fn matched_text(&self, color: Color) -> Option<Box<[String]>> {
+        if color.index() >= self.num_groups() {
+            return None;
+        }
         let (theme, words) = self.board.matched_set_text(color);
-        Box::new([theme.into(), words])
+        Some(Box::new([theme.into(), words]))
+    }
+
+    /// Whether the puzzle's code was signed by a trusted key, see
+    /// [`ConnectionPuzzle::decode_trusted`]
+    pub fn is_verified(&self) -> bool {
+        self.board.puzzle().signer().is_some()
+    }
+
+    /// The name of the trusted key that signed the puzzle's code
+    pub fn verified_by(&self) -> Option<String> {
+        self.board.puzzle().signer().map(Into::into)
+    }
+
+    /// The puzzle's title, intro, end note and so on, for the start and end screens
+    pub fn metadata(&self) -> Metadata {
+        self.board.puzzle().metadata().clone()
     }
 
-    pub fn connection_set(&self, color: Color) -> ConnectionSet {
-        self.board.set(color).clone()
+    /// The `#rrggbb` color of the group, or `None` if the puzzle doesn't have it
+    pub fn group_hex(&self, color: Color) -> Option<String> {
+        let puzzle = self.board.puzzle();
+        (color.index() < puzzle.num_groups()).then(|| puzzle.hex(color).into())
     }
 
-    pub fn clipboard_copied() {
-        console_log!("copied to clipboard");
+    /// `None` if the puzzle doesn't have the group
+    pub fn connection_set(&self, color: Color) -> Option<ConnectionSet> {
+        (color.index() < self.num_groups()).then(|| self.board.set(color).clone())
     }
 
+    /// Every submission so far, see [`GuessLog::to_json`]. Saves don't hold the log, so a
+    /// game picked back up from a save code only has what was submitted since.
+    pub fn log_json(&self) -> String {
+        self.log.to_json(&self.board)
+    }
+
+    /// Like [`Self::log_json`], starting over on a resumed game too
+    pub fn log_csv(&self) -> String {
+        self.log.to_csv(&self.board)
+    }
+
+    /// The next of a group's hints, ending with its theme. With no `color` it's for the
+    /// easiest unsolved group that has any left.
+    pub fn hint(&mut self, color: Option<Color>) -> Result<Hint, HintError> {
+        self.commit(|game| {
+            let nudges = |color: Color| {
+                let set = game.board.set(color);
+                set.hints()
+                    .iter()
+                    .map(String::as_str)
+                    .chain([set.theme_ref()])
+            };
+            let color = game.hint_target(color, |color| {
+                nudges(color)
+                    .nth(game.hints_taken(color, HintKind::Nudge))
+                    .is_some()
+            })?;
+            let level = game.hints_taken(color, HintKind::Nudge);
+            let text = nudges(color)
+                .nth(level)
+                .expect("target has a hint left")
+                .to_string();
+            game.pay_for_hint(color, HintKind::Nudge)?;
+            Ok(Hint::new(color, HintKind::Nudge, &text))
+        })
+    }
+
+    /// Gives away one word of an unsolved group. The last word is never given away, since
+    /// that would solve the group without paying for it.
+    pub fn reveal_word(&mut self, color: Option<Color>) -> Result<Hint, HintError> {
+        self.commit(|game| {
+            let group_size = game.group_size();
+            let color = game.hint_target(color, |color| {
+                game.hints_taken(color, HintKind::Word) + 1 < group_size
+            })?;
+            let index = game.hints_taken(color, HintKind::Word);
+            let card = game
+                .board
+                .group(color)
+                .nth(index)
+                .expect("target has a word left");
+            let word = game.board.puzzle()[card].to_string();
+            game.pay_for_hint(color, HintKind::Word)?;
+            Ok(Hint::new(color, HintKind::Word, &word))
+        })
+    }
+
+    /// The red herrings the last counted guess fell for, which is nothing if it matched
+    pub fn fell_for(&self) -> Vec<Trap> {
+        self.guesses.last().map_or(Vec::new(), |guess| {
+            self.herrings_in(guess)
+                .into_iter()
+                .map(|herring| self.trap(herring))
+                .collect()
+        })
+    }
+
+    /// Every red herring that caught the player, once each, in the order they fell for them
+    pub fn traps(&self) -> Vec<Trap> {
+        let mut caught = Vec::new();
+        for herring in self
+            .guesses
+            .iter()
+            .flat_map(|guess| self.herrings_in(guess))
+        {
+            if !caught.contains(&herring) {
+                caught.push(herring);
+            }
+        }
+        caught
+            .into_iter()
+            .map(|herring| self.trap(herring))
+            .collect()
+    }
+
+    /// Starts at [`MAX_SCORE`] and loses the points every hint taken cost
+    pub fn score(&self) -> u8 {
+        let spent: u32 = self
+            .hints
+            .iter()
+            .map(|hint| u32::from(self.rules.hint_cost(hint.kind).point_cost()))
+            .sum();
+        MAX_SCORE.saturating_sub(spent.try_into().unwrap_or(u8::MAX))
+    }
+
+    /// Whether every group is solved or the mistake limit has been reached
+    pub fn is_over(&self) -> bool {
+        let lost = self
+            .rules
+            .mistake_limit()
+            .is_some_and(|limit| self.mistakes >= limit);
+        usize::from(self.successes) == self.num_groups() || lost
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = reveal_group)]
+    pub fn js_reveal_group(
+        &mut self,
+        color: Option<Color>,
+    ) -> Result<JsSelectionSuccess, HintError> {
+        self.reveal_group(color)
+            .map(|success| self.js_success(success))
+    }
+
+    /// One row of squares per guess, colored by the group each selected card belongs to
+    pub fn result_grid(&self) -> String {
+        let rows: Vec<String> = self
+            .guesses
+            .iter()
+            .map(|guess| {
+                guess
+                    .iter()
+                    .map(|card| {
+                        let color = self.board.group_of(card);
+                        // a lost sealed game never finds out where some cards went
+                        if self.board.puzzle().is_found(color) {
+                            color.emoji()
+                        } else {
+                            "⬜"
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    /// The result to share once the game is over, which doesn't give away any words or
+    /// themes. It names the puzzle by `number` when it has one, and ends with `link` so whoever
+    /// it's shared with can play too. With neither, the puzzle goes by its code.
+    pub fn share_text(&self, number: Option<u32>, link: Option<String>) -> String {
+        let puzzle = match (number, &link) {
+            (Some(number), _) => format!("\nPuzzle #{number}"),
+            (None, Some(_)) => String::new(),
+            (None, None) => format!("\nPuzzle {}", self.puzzle_code()),
+        };
+        let mistakes = match self.rules.mistake_limit() {
+            Some(limit) => format!("Mistakes: {}/{limit}", self.mistakes),
+            None => format!("Mistakes: {}", self.mistakes),
+        };
+        let mut text = format!("Connections{puzzle}\n{}\n{mistakes}", self.result_grid());
+        if !self.hints.is_empty() {
+            let hints: Vec<String> = self.hints.iter().map(UsedHint::emoji).collect();
+            text.push_str(&format!("\nHints: {}", hints.join(" ")));
+        }
+        if self.score() < MAX_SCORE {
+            text.push_str(&format!("\nScore: {}/{MAX_SCORE}", self.score()));
+        }
+        if let Some(link) = link {
+            text.push_str(&format!("\n{link}"));
+        }
+        text
+    }
+
+    /// Copies [`GameState::share_text`] to the clipboard, resolving once it's there. Off the
+    /// main thread there's no window to copy through, and the promise rejects.
+    #[cfg(feature = "wasm")]
+    pub fn copy_result(&self, number: Option<u32>, link: Option<String>) -> js_sys::Promise {
+        let Some(window) = web_sys::window() else {
+            let error = js_sys::Error::new("there's no clipboard to copy the result to");
+            return js_sys::Promise::reject(&error.into());
+        };
+        window
+            .navigator()
+            .clipboard()
+            .write_text(&self.share_text(number, link))
+    }
+
+    #[cfg(feature = "wasm")]
     pub fn get_owned(&self, index: usize) -> OwnedCard {
         self.board.get(index).into()
     }
 
-    pub fn new(puzzle: ConnectionPuzzle) -> Self {
-        let board = Board::new(puzzle);
-        let prev_attempts = Vec::new();
+    /// Plays by the puzzle's own rules unless `rules` overrides them
+    pub fn new(puzzle: ConnectionPuzzle, rules: Option<Rules>) -> Self {
+        Self::seeded(puzzle, rules, rand::random())
+    }
+
+    /// Games with the same puzzle and seed start with the same layout and shuffle the same
+    /// way, see [`seed_from_text`] for seeding from dates or links
+    pub fn seeded(puzzle: ConnectionPuzzle, rules: Option<Rules>, seed: u64) -> Self {
+        let rules = rules.unwrap_or(puzzle.rules());
+        let board = Board::new(puzzle, seed);
         Self {
             mistakes: 0,
             successes: 0,
             board,
-            prev_attempts,
+            guesses: Vec::new(),
+            hints: Vec::new(),
+            rules,
+            history: History::new(),
+            log: GuessLog::new(),
         }
     }
 
     pub fn swap(&mut self, a: usize, b: usize) {
-        self.board.swap(a, b);
+        self.undoable(|game| game.board.swap(a, b));
     }
 }
 
@@ -181,57 +474,324 @@ impl GameState {
             board,
             mistakes: 0,
             successes: 0,
-            prev_attempts: Vec::new(),
+            guesses: Vec::new(),
+            hints: Vec::new(),
+            rules: Rules::STANDARD,
+            history: History::new(),
+            log: GuessLog::new(),
         }
     }
 
-    pub fn get(&self, index: usize) -> Card {
+    pub fn get(&self, index: usize) -> Card<'_> {
         self.board.get(index)
     }
+
+    pub fn puzzle(&self) -> &ConnectionPuzzle {
+        self.board.puzzle()
+    }
+
+    pub fn guesses(&self) -> &[Selection] {
+        &self.guesses
+    }
+
+    pub fn log(&self) -> &GuessLog {
+        &self.log
+    }
+
+    pub fn save(&self) -> SaveState {
+        SaveState::new(
+            self.board.puzzle(),
+            self.rules,
+            self.seed(),
+            self.snapshot(),
+        )
+    }
+
+    /// Picks a saved game back up, as long as it was saved from a game of `puzzle`. Its
+    /// [`GuessLog`] starts out empty, since saves leave it out.
+    pub fn resume(mut puzzle: ConnectionPuzzle, save: SaveState) -> Result<Self, SaveError> {
+        let (rules, seed) = (save.rules(), save.seed());
+        // a sealed puzzle only knows the groups that were found by guessing them again
+        for guess in save.guesses() {
+            puzzle.unseal(&guess.iter().collect::<Vec<_>>());
+        }
+        let state = save.check(&puzzle)?;
+        let mut game = Self::seeded(puzzle, Some(rules), seed);
+        game.restore(state);
+        Ok(game)
+    }
+
+    pub fn check_selection(&mut self) -> Result<SelectionSuccess, Failiure> {
+        self.commit(Self::judge_selection)
+    }
+
+    /// Solves a group for the player, or the easiest unsolved one when `color` is `None`
+    pub fn reveal_group(&mut self, color: Option<Color>) -> Result<SelectionSuccess, HintError> {
+        self.commit(|game| {
+            let color = game.hint_target(color, |_| true)?;
+            game.pay_for_hint(color, HintKind::Group)?;
+            game.board.mark_matched(color);
+            game.successes += 1;
+            if game.is_over() {
+                Ok(SelectionSuccess::Won(color))
+            } else {
+                Ok(SelectionSuccess::Matched(color))
+            }
+        })
+    }
+
+    pub fn hints_used(&self) -> &[UsedHint] {
+        &self.hints
+    }
+
+    /// Runs `action` for good, unless the rules allow practice and it can be undone
+    fn commit<R>(&mut self, action: impl FnOnce(&mut Self) -> R) -> R {
+        if self.rules.is_practice() {
+            return self.undoable(action);
+        }
+        let before = self.snapshot();
+        let result = action(self);
+        if before != self.snapshot() {
+            // outside of practice a guess is final, so nothing before it can be undone either
+            self.history.clear();
+        }
+        result
+    }
+
+    /// Picks the group a hint is about. With no `color` it's the easiest unsolved group
+    /// that `has_more` to give away.
+    fn hint_target(
+        &self,
+        color: Option<Color>,
+        has_more: impl Fn(Color) -> bool,
+    ) -> Result<Color, HintError> {
+        if self.is_over() {
+            return Err(HintError::GameOver);
+        }
+        if self.board.puzzle().is_sealed() {
+            return Err(HintError::Sealed);
+        }
+        match color {
+            Some(color) if color.index() >= self.num_groups() => Err(HintError::NoSuchGroup),
+            Some(color) if self.board.is_matched(color) => Err(HintError::AlreadySolved),
+            Some(color) if !has_more(color) => Err(HintError::NoneLeft),
+            Some(color) => Ok(color),
+            None => self
+                .board
+                .puzzle()
+                .by_difficulty()
+                .into_iter()
+                .find(|&color| !self.board.is_matched(color) && has_more(color))
+                .ok_or(HintError::NoneLeft),
+        }
+    }
+
+    /// Takes a hint's cost, refusing it if it would use up the last mistake
+    fn pay_for_hint(&mut self, color: Color, kind: HintKind) -> Result<(), HintError> {
+        let cost = self.rules.hint_cost(kind).mistake_cost();
+        if self
+            .mistakes_remaining()
+            .is_some_and(|remaining| remaining <= cost)
+        {
+            return Err(HintError::TooCostly);
+        }
+        self.mistakes = self.mistakes.saturating_add(cost);
+        self.hints.push(UsedHint { color, kind });
+        Ok(())
+    }
+
+    fn hints_taken(&self, color: Color, kind: HintKind) -> usize {
+        let hint = UsedHint { color, kind };
+        self.hints.iter().filter(|&&used| used == hint).count()
+    }
+
+    /// Runs `action`, and makes it undoable if it changed anything
+    fn undoable<R>(&mut self, action: impl FnOnce(&mut Self) -> R) -> R {
+        let before = self.snapshot();
+        let result = action(self);
+        if before != self.snapshot() {
+            self.history.record(before);
+        }
+        result
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            mistakes: self.mistakes,
+            successes: self.successes,
+            guesses: self.guesses.clone(),
+            hints: self.hints.clone(),
+            board: self.board.snapshot(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.mistakes = snapshot.mistakes;
+        self.successes = snapshot.successes;
+        self.guesses = snapshot.guesses;
+        self.hints = snapshot.hints;
+        self.board.replay_seal(&self.guesses);
+        self.board.restore(snapshot.board);
+    }
+
+    /// The red herrings in a guess that missed, when most of the guess went with the group
+    /// the herring was planted to look like it belongs to
+    fn herrings_in(&self, guess: &Selection) -> Vec<(PuzzleRef, Color)> {
+        let in_group = |color| {
+            guess
+                .iter()
+                .filter(|&card| self.board.group_of(card) == color)
+                .count()
+        };
+        let most = guess
+            .iter()
+            .map(|card| in_group(self.board.group_of(card)))
+            .max()
+            .unwrap_or(0);
+        if most == guess.len() {
+            return Vec::new();
+        }
+        guess
+            .iter()
+            .filter_map(|card| {
+                let set = self.board.set(card.color());
+                let decoy = set.herring_for(card.word_index())?;
+                (in_group(decoy) == most).then_some((card, decoy))
+            })
+            .collect()
+    }
+
+    fn trap(&self, (card, decoy): (PuzzleRef, Color)) -> Trap {
+        let puzzle = self.board.puzzle();
+        Trap::new(&puzzle[card], decoy, puzzle.by_color(decoy).theme_ref())
+    }
+
+    fn judge_selection(&mut self) -> Result<SelectionSuccess, Failiure> {
+        let guess = self.board.selection.clone();
+        let (outcome, result) = self.judge_guess(guess.clone());
+        self.log.record(guess, outcome, self.mistakes_remaining());
+        result
+    }
+
+    fn judge_guess(
+        &mut self,
+        guess: Selection,
+    ) -> (GuessOutcome, Result<SelectionSuccess, Failiure>) {
+        use Failiure::*;
+        use SelectionSuccess::{Matched, Won};
+
+        if self.is_over() {
+            return (GuessOutcome::GameOver, Err(GameOver));
+        }
+        if self.rules.block_repeats() && self.guesses.contains(&guess) {
+            return (GuessOutcome::AlreadyTried, Err(AlreadyTried));
+        }
+
+        let almost_won = usize::from(self.successes) + 1 == self.board.num_groups();
+
+        let almost_lost = self
+            .rules
+            .mistake_limit()
+            .is_some_and(|limit| self.mistakes.saturating_add(1) >= limit);
+
+        match self.board.test_selection() {
+            Ok(color) => {
+                self.guesses.push(guess);
+                self.successes += 1;
+                self.swap_on_success();
+                let result = if almost_won {
+                    Ok(Won(color))
+                } else {
+                    Ok(Matched(color))
+                };
+                (GuessOutcome::Matched(color), result)
+            }
+            Err(SelectionFailiure::Mismatch) => {
+                self.guesses.push(guess);
+                self.record_mistake();
+
+                let result = if almost_lost {
+                    Err(Lost)
+                } else {
+                    Err(Mismatch)
+                };
+                (GuessOutcome::Mismatch, result)
+            }
+            Err(SelectionFailiure::OneAway) => {
+                self.guesses.push(guess);
+                self.record_mistake();
+                let result = if almost_lost {
+                    Err(Lost)
+                } else if self.rules.one_away_feedback() {
+                    Err(OneAway)
+                } else {
+                    Err(Mismatch)
+                };
+                (GuessOutcome::OneAway, result)
+            }
+            Err(SelectionFailiure::NotEnough) => (GuessOutcome::NotEnough, Err(NotEnough)),
+        }
+    }
+}
+
+/// Turns any text, like a date or a puzzle code, into a seed for [`GameState::seeded`]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn seed_from_text(text: &str) -> u64 {
+    // FNV-1a, which is stable across platforms and versions unlike std's hasher
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
 }
 
 #[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SelectionSuccess {
     Won(Color),
     Matched(Color),
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Copy, Clone)]
 pub enum SelectionSuccessTags {
     Won,
     Matched,
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub struct JsSelectionSuccess {
+    pub color: Color,
+    /// What to call the group, see [`ConnectionPuzzle::label`]
     #[wasm_bindgen(getter_with_clone)]
-    pub color: js_sys::JsString,
+    pub label: js_sys::JsString,
     pub result: SelectionSuccessTags,
 }
 
-impl From<SelectionSuccess> for JsSelectionSuccess {
-    fn from(other: SelectionSuccess) -> Self {
-        match other {
-            SelectionSuccess::Won(color) => Self {
-                color: color.into(),
-                result: SelectionSuccessTags::Won,
-            },
-            SelectionSuccess::Matched(color) => Self {
-                color: color.into(),
-                result: SelectionSuccessTags::Matched,
-            },
+#[cfg(feature = "wasm")]
+impl GameState {
+    fn js_success(&self, success: SelectionSuccess) -> JsSelectionSuccess {
+        let (color, result) = match success {
+            SelectionSuccess::Won(color) => (color, SelectionSuccessTags::Won),
+            SelectionSuccess::Matched(color) => (color, SelectionSuccessTags::Matched),
+        };
+        JsSelectionSuccess {
+            color,
+            label: self.board.puzzle().label(color).into(),
+            result,
         }
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Failiure {
     Mismatch,
     NotEnough,
     OneAway,
     Lost,
     AlreadyTried,
+    /// The game was already won or lost, so the guess didn't count
+    GameOver,
 }
 
 impl From<SelectionFailiure> for Failiure {
@@ -243,3 +803,407 @@ impl From<SelectionFailiure> for Failiure {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alternative::{Alternative, CardMove};
+    use hints::HintCost;
+
+    const YELLOW: [&str; 4] = ["wheat", "milk", "eggs", "sugar"];
+    const BLUE: [&str; 4] = ["helium", "argon", "krypton", "neon"];
+    const MISS: [&str; 4] = ["wheat", "helium", "war", "victory"];
+    const ONE_AWAY: [&str; 4] = ["wheat", "milk", "eggs", "neon"];
+
+    fn select(game: &mut GameState, words: &[&str]) {
+        for index in 0..game.num_cards() {
+            if words.contains(&game.card_text(index).as_str()) {
+                game.select(index).unwrap();
+            }
+        }
+    }
+
+    /// Submits `words` and clears the selection. The log's tests play through this too.
+    pub(super) fn guess(
+        game: &mut GameState,
+        words: &[&str],
+    ) -> Result<SelectionSuccess, Failiure> {
+        select(game, words);
+        let result = game.check_selection();
+        game.clear_selection();
+        result
+    }
+
+    fn game(rules: Rules) -> GameState {
+        GameState::new(ConnectionPuzzle::default(), Some(rules))
+    }
+
+    /// A different miss every time, so repeats being blocked doesn't get in the way
+    fn misses() -> impl Iterator<Item = [&'static str; 4]> {
+        ["war", "bed", "situation", "clean"]
+            .into_iter()
+            .map(|purple| ["wheat", "helium", purple, "victory"])
+    }
+
+    #[test]
+    fn standard_rules_lose_on_the_fourth_mistake() {
+        let mut game = game(Rules::standard());
+        let mut misses = misses();
+        for left in (1..4).rev() {
+            assert_eq!(
+                guess(&mut game, &misses.next().unwrap()),
+                Err(Failiure::Mismatch)
+            );
+            assert_eq!(game.mistakes_remaining(), Some(left));
+        }
+        assert_eq!(
+            guess(&mut game, &misses.next().unwrap()),
+            Err(Failiure::Lost)
+        );
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn zen_never_loses() {
+        let mut game = game(Rules::zen());
+        for miss in misses().chain([ONE_AWAY]) {
+            assert!(guess(&mut game, &miss).is_err());
+        }
+        assert_eq!(game.mistakes(), 5);
+        assert_eq!(game.mistakes_remaining(), None);
+        assert!(!game.is_over());
+        assert_eq!(
+            guess(&mut game, &YELLOW),
+            Ok(SelectionSuccess::Matched(Color::Yellow))
+        );
+    }
+
+    #[test]
+    fn nothing_counts_once_the_game_is_over() {
+        let mut game = game(Rules::sudden_death());
+        assert_eq!(guess(&mut game, &MISS), Err(Failiure::Lost));
+        let before = game.snapshot();
+        assert_eq!(guess(&mut game, &YELLOW), Err(Failiure::GameOver));
+        assert_eq!(game.snapshot().board, before.board);
+        assert_eq!(game.guesses().len(), 1);
+        assert_eq!(game.log().entries()[1].outcome, GuessOutcome::GameOver);
+    }
+
+    #[test]
+    fn one_away_feedback_can_be_turned_off() {
+        let mut game = game(Rules::standard().with_one_away_feedback(false));
+        assert_eq!(guess(&mut game, &ONE_AWAY), Err(Failiure::Mismatch));
+        let mut game = self::game(Rules::standard());
+        assert_eq!(guess(&mut game, &ONE_AWAY), Err(Failiure::OneAway));
+    }
+
+    #[test]
+    fn repeats_are_blocked_unless_allowed() {
+        let mut game = game(Rules::standard());
+        let _ = guess(&mut game, &MISS);
+        assert_eq!(guess(&mut game, &MISS), Err(Failiure::AlreadyTried));
+        assert_eq!(game.mistakes(), 1);
+
+        let mut game = self::game(Rules::standard().with_repeats_blocked(false));
+        let _ = guess(&mut game, &MISS);
+        assert_eq!(guess(&mut game, &MISS), Err(Failiure::Mismatch));
+        assert_eq!(game.mistakes(), 2);
+    }
+
+    #[test]
+    fn puzzles_bring_their_own_rules() {
+        let puzzle = ConnectionPuzzle::default().with_rules(Rules::sudden_death());
+        let puzzle = ConnectionPuzzle::decode(&puzzle.encode()).unwrap();
+        assert_eq!(
+            GameState::new(puzzle.clone(), None).rules(),
+            Rules::sudden_death()
+        );
+        let game = GameState::new(puzzle, Some(Rules::zen()));
+        assert_eq!(game.rules(), Rules::zen());
+    }
+
+    #[test]
+    fn winning_takes_every_group() {
+        let mut game = game(Rules::standard());
+        let _ = guess(&mut game, &YELLOW);
+        let _ = guess(&mut game, &BLUE);
+        let _ = guess(&mut game, &["war", "bed", "situation", "clean"]);
+        let won = guess(&mut game, &["victory", "ocean", "thunder", "music"]);
+        assert_eq!(won, Ok(SelectionSuccess::Won(Color::Green)));
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn undo_and_redo_selections_and_shuffles() {
+        let mut game = game(Rules::standard());
+        assert!(!game.can_undo());
+        let layout = game.board.snapshot();
+        game.select(0).unwrap();
+        game.shuffle();
+        assert!(game.undo());
+        assert_eq!(game.board.selection.len(), 1);
+        assert!(game.undo());
+        assert_eq!(game.board.snapshot(), layout);
+        assert!(!game.undo());
+        assert!(game.redo() && game.redo());
+        assert!(!game.redo());
+        assert_eq!(game.board.selection.len(), 1);
+    }
+
+    #[test]
+    fn guesses_are_final_outside_of_practice() {
+        let mut game = game(Rules::standard());
+        select(&mut game, &YELLOW);
+        let _ = game.check_selection();
+        assert!(!game.can_undo());
+        assert!(game.board.is_matched(Color::Yellow));
+    }
+
+    #[test]
+    fn practice_undoes_a_match() {
+        let mut game = game(Rules::practice());
+        select(&mut game, &YELLOW);
+        let _ = game.check_selection();
+        assert!(game.undo());
+        assert!(!game.board.is_matched(Color::Yellow));
+        assert_eq!(game.board.selection.len(), 4);
+        assert!(game.guesses().is_empty());
+        assert!(game.redo());
+        assert!(game.board.is_matched(Color::Yellow));
+    }
+
+    #[test]
+    fn a_new_action_forgets_what_could_be_redone() {
+        let mut game = game(Rules::standard());
+        game.select(0).unwrap();
+        assert!(game.undo());
+        game.shuffle();
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn save_code_round_trips() {
+        let mut game = game(Rules::standard());
+        let _ = guess(&mut game, &YELLOW);
+        let _ = guess(&mut game, &MISS);
+        game.select(0).unwrap();
+        let code = game.save_code();
+        let resumed = GameState::resume_code(ConnectionPuzzle::default(), &code).unwrap();
+        assert_eq!(resumed.snapshot(), game.snapshot());
+        assert_eq!(resumed.seed(), game.seed());
+        assert_eq!(resumed.rules(), game.rules());
+    }
+
+    #[test]
+    fn saves_only_resume_their_own_puzzle() {
+        let code = game(Rules::standard()).save_code();
+        let other = ConnectionPuzzle::decode("debug").unwrap();
+        assert_eq!(
+            GameState::resume_code(other, &code).unwrap_err(),
+            SaveError::WrongPuzzle
+        );
+        assert_eq!(
+            GameState::resume_code(ConnectionPuzzle::default(), "not a save").unwrap_err(),
+            SaveError::Encoding
+        );
+    }
+
+    #[test]
+    fn a_fixed_seed_gives_a_fixed_shuffle() {
+        let layout = |game: &GameState| -> Vec<String> {
+            (0..game.num_cards())
+                .map(|index| game.card_text(index))
+                .collect()
+        };
+        let seeded = || GameState::seeded(ConnectionPuzzle::default(), None, 42);
+        let (mut a, mut b) = (seeded(), seeded());
+        assert_eq!(layout(&a), layout(&b));
+        a.shuffle();
+        b.shuffle();
+        assert_eq!(layout(&a), layout(&b));
+        let other = GameState::seeded(ConnectionPuzzle::default(), None, 43);
+        assert_ne!(layout(&other), layout(&seeded()));
+    }
+
+    #[test]
+    fn seeds_from_text_are_stable() {
+        assert_eq!(seed_from_text(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(seed_from_text("2024-06-01"), seed_from_text("2024-06-01"));
+        assert_ne!(seed_from_text("2024-06-01"), seed_from_text("2024-06-02"));
+    }
+
+    #[test]
+    fn shared_results_give_nothing_away() {
+        let mut game = game(Rules::standard());
+        let _ = guess(&mut game, &ONE_AWAY);
+        let _ = guess(&mut game, &YELLOW);
+        let link = "https://example.com/?game=abc";
+        let text = game.share_text(Some(7), Some(link.into()));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[..2], ["Connections", "Puzzle #7"]);
+        assert_eq!(lines[2].matches('🟨').count(), 3);
+        assert_eq!(lines[3], "🟨🟨🟨🟨");
+        assert_eq!(lines.last(), Some(&link));
+        assert!(!["wheat", "neon", "Minecraft"]
+            .iter()
+            .any(|word| text.contains(word)));
+        let unnumbered = game.share_text(None, Some(link.into()));
+        assert!(!unnumbered.contains("Puzzle"));
+    }
+
+    #[test]
+    fn nudges_end_with_the_theme() {
+        let mut game = game(Rules::standard());
+        let nudges: Vec<String> = (0..3)
+            .map(|_| game.hint(Some(Color::Yellow)).unwrap().text())
+            .collect();
+        assert_eq!(nudges, ["Baking", "Video games", "Minecraft Cake Recipe"]);
+        assert_eq!(
+            game.hint(Some(Color::Yellow)).unwrap_err(),
+            HintError::NoneLeft
+        );
+        assert_eq!(game.score(), MAX_SCORE - 30);
+    }
+
+    #[test]
+    fn hints_go_to_the_easiest_unsolved_group() {
+        let mut game = game(Rules::standard());
+        let _ = guess(&mut game, &YELLOW);
+        // green is easier than blue in the NYT's order
+        assert_eq!(game.hint(None).unwrap().color(), Color::Green);
+        let word = game.reveal_word(None).unwrap();
+        assert_eq!(word.text(), "victory");
+        assert_eq!(
+            game.hint(Some(Color::Yellow)).unwrap_err(),
+            HintError::AlreadySolved
+        );
+        assert_eq!(
+            game.hint(Some(Color::Red)).unwrap_err(),
+            HintError::NoSuchGroup
+        );
+    }
+
+    #[test]
+    fn the_last_word_is_never_given_away() {
+        let mut game = game(Rules::standard());
+        for _ in 0..3 {
+            game.reveal_word(Some(Color::Blue)).unwrap();
+        }
+        assert_eq!(
+            game.reveal_word(Some(Color::Blue)).unwrap_err(),
+            HintError::NoneLeft
+        );
+    }
+
+    #[test]
+    fn revealing_a_group_costs_a_mistake() {
+        let mut game = game(Rules::standard());
+        let revealed = game.reveal_group(Some(Color::Purple)).unwrap();
+        assert_eq!(revealed, SelectionSuccess::Matched(Color::Purple));
+        assert_eq!(game.mistakes(), 1);
+        assert!(game.share_text(None, None).contains("🟪🔓"));
+        let rules = Rules::sudden_death();
+        let mut game = self::game(rules);
+        assert_eq!(game.reveal_group(None).unwrap_err(), HintError::TooCostly);
+        let mut game = self::game(rules.with_hint_cost(HintKind::Group, HintCost::FREE));
+        assert!(game.reveal_group(None).is_ok());
+    }
+
+    #[test]
+    fn no_hints_once_the_game_is_over() {
+        let mut game = game(Rules::sudden_death());
+        let _ = guess(&mut game, &MISS);
+        assert_eq!(game.hint(None).unwrap_err(), HintError::GameOver);
+    }
+
+    fn swapped() -> GameState {
+        let swap = Alternative::new(vec![
+            CardMove::new(Color::Yellow, 0, Color::Blue),
+            CardMove::new(Color::Blue, 0, Color::Yellow),
+        ]);
+        let puzzle = ConnectionPuzzle::default().with_alternative(swap).unwrap();
+        GameState::new(puzzle, None)
+    }
+
+    #[test]
+    fn either_solution_matches() {
+        let mut game = swapped();
+        assert_eq!(
+            guess(&mut game, &YELLOW),
+            Ok(SelectionSuccess::Matched(Color::Yellow))
+        );
+        let mut game = swapped();
+        let yellow = ["helium", "milk", "eggs", "sugar"];
+        assert_eq!(
+            guess(&mut game, &yellow),
+            Ok(SelectionSuccess::Matched(Color::Yellow))
+        );
+        assert_eq!(game.mistakes(), 0);
+    }
+
+    #[test]
+    fn a_match_settles_which_solution_is_played() {
+        let mut game = swapped();
+        let _ = guess(&mut game, &["helium", "milk", "eggs", "sugar"]);
+        // wheat goes with the noble gasses now that helium's taken
+        let one_away = ["wheat", "argon", "krypton", "war"];
+        assert_eq!(guess(&mut game, &one_away), Err(Failiure::OneAway));
+        let blue = ["wheat", "argon", "krypton", "neon"];
+        assert_eq!(
+            guess(&mut game, &blue),
+            Ok(SelectionSuccess::Matched(Color::Blue))
+        );
+    }
+
+    fn sealed() -> GameState {
+        let code = ConnectionPuzzle::default().encode_sealed();
+        GameState::new(
+            ConnectionPuzzle::decode(&code).unwrap(),
+            Some(Rules::practice()),
+        )
+    }
+
+    fn found(game: &GameState) -> Vec<bool> {
+        game.puzzle()
+            .colors()
+            .map(|color| game.puzzle().is_found(color))
+            .collect()
+    }
+
+    #[test]
+    fn starting_over_seals_the_puzzle_again() {
+        let mut game = sealed();
+        let Ok(SelectionSuccess::Matched(color)) = guess(&mut game, &YELLOW) else {
+            panic!("yellow is a group");
+        };
+        assert_eq!(
+            game.puzzle().by_color(color).theme_ref(),
+            "Minecraft Cake Recipe"
+        );
+        game.start_over();
+        assert_eq!(found(&game), [false; 4]);
+        assert!(game
+            .puzzle()
+            .sets()
+            .iter()
+            .all(|set| set.theme_ref().is_empty()));
+        assert!(guess(&mut game, &YELLOW).is_ok());
+    }
+
+    #[test]
+    fn undoing_a_sealed_match_hides_its_theme() {
+        let mut game = sealed();
+        let _ = guess(&mut game, &YELLOW);
+        let layout = game.board.snapshot();
+        assert!(game.undo());
+        assert_eq!(found(&game), [false; 4]);
+        assert!(game
+            .puzzle()
+            .sets()
+            .iter()
+            .all(|set| set.theme_ref().is_empty()));
+        assert!(game.redo());
+        assert_eq!(found(&game).iter().filter(|&&found| found).count(), 1);
+        assert_eq!(game.board.snapshot(), layout);
+    }
+}
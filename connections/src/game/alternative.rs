@@ -0,0 +1,97 @@
+use super::color::Color;
+use super::puzzle::PuzzleRef;
+use serde::{Deserialize, Serialize};
+
+/// Another way of splitting a puzzle's cards into groups that also solves it, for words that
+/// honestly fit more than one group. It's written as the cards that go somewhere other than
+/// the group they're written in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Alternative {
+    moves: Vec<CardMove>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CardMove {
+    pub card: PuzzleRef,
+    pub to: Color,
+}
+
+impl CardMove {
+    /// Moves word `word_index` of the `from` group into the `to` group
+    pub fn new(from: Color, word_index: usize, to: Color) -> Self {
+        Self {
+            card: PuzzleRef::new(from, word_index),
+            to,
+        }
+    }
+}
+
+impl Alternative {
+    pub fn new(moves: Vec<CardMove>) -> Self {
+        Self { moves }
+    }
+
+    pub fn moves(&self) -> &[CardMove] {
+        &self.moves
+    }
+
+    pub fn group_of(&self, card: PuzzleRef) -> Color {
+        self.moves
+            .iter()
+            .find(|moved| moved.card == card)
+            .map_or(card.color(), |moved| moved.to)
+    }
+
+    /// Whether this is a different solution to a puzzle of `num_groups` groups of
+    /// `group_size` words, with every group still the same size
+    pub fn fits(&self, num_groups: usize, group_size: usize) -> bool {
+        let in_puzzle = |color: Color| color.index() < num_groups;
+        let real_moves = self.moves.iter().enumerate().all(|(index, moved)| {
+            in_puzzle(moved.card.color())
+                && in_puzzle(moved.to)
+                && moved.card.word_index() < group_size
+                && moved.to != moved.card.color()
+                && !self.moves[..index]
+                    .iter()
+                    .any(|other| other.card == moved.card)
+        });
+        if self.moves.is_empty() || !real_moves {
+            return false;
+        }
+
+        let mut sizes = vec![group_size; num_groups];
+        for moved in &self.moves {
+            sizes[moved.card.color().index()] -= 1;
+            sizes[moved.to.index()] += 1;
+        }
+        sizes.iter().all(|&size| size == group_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_even_swaps_fit() {
+        let swap = Alternative::new(vec![
+            CardMove::new(Color::Yellow, 0, Color::Blue),
+            CardMove::new(Color::Blue, 0, Color::Yellow),
+        ]);
+        assert!(swap.fits(4, 4));
+        assert_eq!(swap.group_of(PuzzleRef::new(Color::Yellow, 0)), Color::Blue);
+        assert_eq!(
+            swap.group_of(PuzzleRef::new(Color::Yellow, 1)),
+            Color::Yellow
+        );
+
+        let lopsided = Alternative::new(vec![CardMove::new(Color::Yellow, 0, Color::Blue)]);
+        assert!(!lopsided.fits(4, 4));
+        assert!(!Alternative::new(Vec::new()).fits(4, 4));
+        let outside = Alternative::new(vec![
+            CardMove::new(Color::Yellow, 0, Color::Red),
+            CardMove::new(Color::Red, 0, Color::Yellow),
+        ]);
+        assert!(!outside.fits(4, 4));
+    }
+}
@@ -0,0 +1,303 @@
+use super::puzzle::ConnectionPuzzle;
+#[cfg(feature = "wasm")]
+use chrono::FixedOffset;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Puzzles with the day each one is published. Puzzles are numbered from 1 in date order, so
+/// the first day's puzzle is #1. They're published in date order too, so a puzzle's number
+/// never changes once it's out.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "ArchiveEntries")]
+pub struct PuzzleArchive {
+    puzzles: Vec<ArchiveEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ArchiveEntry {
+    date: NaiveDate,
+    puzzle: ConnectionPuzzle,
+}
+
+/// What an archive looks like on the wire before its dates have been checked
+#[derive(Deserialize)]
+struct ArchiveEntries {
+    puzzles: Vec<ArchiveEntry>,
+}
+
+impl TryFrom<ArchiveEntries> for PuzzleArchive {
+    type Error = ArchiveError;
+
+    fn try_from(ArchiveEntries { puzzles }: ArchiveEntries) -> Result<Self, ArchiveError> {
+        let mut archive = Self::new();
+        for ArchiveEntry { date, puzzle } in puzzles {
+            archive.publish(date, puzzle)?;
+        }
+        Ok(archive)
+    }
+}
+
+/// A puzzle along with where it sits in the archive
+#[derive(Debug, Clone, Copy)]
+pub struct DailyPuzzle<'a> {
+    pub number: u32,
+    pub date: NaiveDate,
+    pub puzzle: &'a ConnectionPuzzle,
+}
+
+impl PuzzleArchive {
+    pub const fn new() -> Self {
+        Self {
+            puzzles: Vec::new(),
+        }
+    }
+
+    /// Schedules `puzzle` for `date` as the next puzzle. Each day only gets one puzzle, and a
+    /// day before the last one scheduled would renumber every puzzle after it, so it's turned
+    /// away.
+    pub fn publish(
+        &mut self,
+        date: NaiveDate,
+        puzzle: ConnectionPuzzle,
+    ) -> Result<(), ArchiveError> {
+        match self.puzzles.last() {
+            Some(newest) if newest.date == date => Err(ArchiveError::DuplicateDate(date)),
+            Some(newest) if newest.date > date => Err(ArchiveError::Backdated(date)),
+            _ => {
+                self.puzzles.push(ArchiveEntry { date, puzzle });
+                Ok(())
+            }
+        }
+    }
+
+    /// The puzzle for a calendar day, wherever that day is
+    pub fn on(&self, date: NaiveDate) -> Result<DailyPuzzle<'_>, ArchiveError> {
+        self.puzzles
+            .binary_search_by_key(&date, |entry| entry.date)
+            .map(|index| self.daily(index))
+            .map_err(|_| ArchiveError::NoPuzzle(date))
+    }
+
+    /// The puzzle for the day it is at `instant` in the instant's time zone, so players in
+    /// different zones can be on different puzzles at the same moment
+    pub fn at<Tz: TimeZone>(
+        &self,
+        instant: &DateTime<Tz>,
+    ) -> Result<DailyPuzzle<'_>, ArchiveError> {
+        self.on(instant.date_naive())
+    }
+
+    pub fn today<Tz: TimeZone>(&self, zone: &Tz) -> Result<DailyPuzzle<'_>, ArchiveError> {
+        self.at(&Utc::now().with_timezone(zone))
+    }
+
+    pub fn number(&self, number: u32) -> Result<DailyPuzzle<'_>, ArchiveError> {
+        let index = (number as usize)
+            .checked_sub(1)
+            .filter(|&index| index < self.puzzles.len())
+            .ok_or(ArchiveError::NoNumber(number))?;
+        Ok(self.daily(index))
+    }
+
+    /// Every puzzle published on or before `date`, oldest first. Puzzles scheduled for later
+    /// days are left out so they aren't spoiled.
+    pub fn published_by(&self, date: NaiveDate) -> impl Iterator<Item = DailyPuzzle<'_>> {
+        let published = self.puzzles.partition_point(|entry| entry.date <= date);
+        (0..published).map(|index| self.daily(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = DailyPuzzle<'_>> {
+        (0..self.puzzles.len()).map(|index| self.daily(index))
+    }
+
+    fn daily(&self, index: usize) -> DailyPuzzle<'_> {
+        let entry = &self.puzzles[index];
+        DailyPuzzle {
+            number: index as u32 + 1,
+            date: entry.date,
+            puzzle: &entry.puzzle,
+        }
+    }
+}
+
+/// Dates cross into JS as `YYYY-MM-DD` strings and time zones as minutes ahead of UTC, which
+/// is `-new Date().getTimezoneOffset()`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl PuzzleArchive {
+    pub fn from_json(json: &str) -> Result<PuzzleArchive, ArchiveError> {
+        serde_json::from_str(json).map_err(|_| ArchiveError::Format)
+    }
+
+    pub fn len(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.puzzles.is_empty()
+    }
+
+    #[wasm_bindgen(js_name = today)]
+    pub fn js_today(&self, utc_offset_minutes: i32) -> Result<ConnectionPuzzle, ArchiveError> {
+        self.today(&js_zone(utc_offset_minutes)?)
+            .map(|daily| daily.puzzle.clone())
+    }
+
+    pub fn today_number(&self, utc_offset_minutes: i32) -> Result<u32, ArchiveError> {
+        self.today(&js_zone(utc_offset_minutes)?)
+            .map(|daily| daily.number)
+    }
+
+    pub fn puzzle_on(&self, date: &str) -> Result<ConnectionPuzzle, ArchiveError> {
+        self.on(js_date(date)?).map(|daily| daily.puzzle.clone())
+    }
+
+    pub fn number_on(&self, date: &str) -> Result<u32, ArchiveError> {
+        self.on(js_date(date)?).map(|daily| daily.number)
+    }
+
+    pub fn puzzle_number(&self, number: u32) -> Result<ConnectionPuzzle, ArchiveError> {
+        self.number(number).map(|daily| daily.puzzle.clone())
+    }
+
+    pub fn date_of(&self, number: u32) -> Result<String, ArchiveError> {
+        self.number(number).map(|daily| daily.date.to_string())
+    }
+
+    /// The numbers of every puzzle published by `date`, oldest first
+    pub fn published_numbers(&self, date: &str) -> Result<Vec<u32>, ArchiveError> {
+        Ok(self
+            .published_by(js_date(date)?)
+            .map(|daily| daily.number)
+            .collect())
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn js_date(date: &str) -> Result<NaiveDate, ArchiveError> {
+    date.parse().map_err(|_| ArchiveError::InvalidDate)
+}
+
+#[cfg(feature = "wasm")]
+fn js_zone(utc_offset_minutes: i32) -> Result<FixedOffset, ArchiveError> {
+    FixedOffset::east_opt(utc_offset_minutes * 60).ok_or(ArchiveError::InvalidDate)
+}
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveError {
+    #[error("there's no puzzle for {0}")]
+    NoPuzzle(NaiveDate),
+    #[error("there's no puzzle #{0}")]
+    NoNumber(u32),
+    #[error("there's already a puzzle for {0}")]
+    DuplicateDate(NaiveDate),
+    #[error("{0} is before the last puzzle's date, and puzzles can't be renumbered")]
+    Backdated(NaiveDate),
+    #[error("not a date or time zone")]
+    InvalidDate,
+    #[error("couldn't read the archive")]
+    Format,
+}
+
+#[cfg(feature = "wasm")]
+impl From<ArchiveError> for JsValue {
+    fn from(error: ArchiveError) -> Self {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::color::Color;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    fn archive() -> PuzzleArchive {
+        let mut archive = PuzzleArchive::new();
+        for date in [day(1), day(2), day(4)] {
+            archive.publish(date, ConnectionPuzzle::default()).unwrap();
+        }
+        archive
+    }
+
+    #[test]
+    fn picks_the_puzzle_for_a_day() {
+        let archive = archive();
+        assert_eq!(archive.on(day(4)).unwrap().number, 3);
+        assert_eq!(archive.number(2).unwrap().date, day(2));
+        assert_eq!(
+            archive.on(day(3)).unwrap_err(),
+            ArchiveError::NoPuzzle(day(3))
+        );
+        assert_eq!(archive.number(4).unwrap_err(), ArchiveError::NoNumber(4));
+    }
+
+    #[test]
+    fn days_depend_on_the_time_zone() {
+        let archive = archive();
+        let instant = Utc.with_ymd_and_hms(2026, 10, 2, 2, 0, 0).unwrap();
+        let behind = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
+        assert_eq!(archive.at(&instant).unwrap().number, 2);
+        assert_eq!(
+            archive.at(&instant.with_timezone(&behind)).unwrap().number,
+            1
+        );
+    }
+
+    #[test]
+    fn lists_only_what_is_published() {
+        let numbers: Vec<u32> = archive().published_by(day(3)).map(|d| d.number).collect();
+        assert_eq!(numbers, [1, 2]);
+    }
+
+    #[test]
+    fn numbers_never_change() {
+        let mut archive = archive();
+        let puzzle = ConnectionPuzzle::default();
+        assert_eq!(
+            archive.publish(day(3), puzzle.clone()).unwrap_err(),
+            ArchiveError::Backdated(day(3))
+        );
+        assert_eq!(
+            archive.publish(day(4), puzzle.clone()).unwrap_err(),
+            ArchiveError::DuplicateDate(day(4))
+        );
+        archive.publish(day(5), puzzle).unwrap();
+        assert_eq!(archive.on(day(4)).unwrap().number, 3);
+        assert_eq!(archive.on(day(5)).unwrap().number, 4);
+    }
+
+    #[test]
+    fn json_puzzles_are_checked_like_codes() {
+        let herring = r#"{"puzzles": [{"date": "2026-10-01", "puzzle": {"sets": [
+            {"theme": "a", "words": ["a", "b"], "herrings": [{"word_index": 9, "decoy": "Blue"}]},
+            {"theme": "b", "words": ["c", "d"]}
+        ]}}]}"#;
+        assert!(serde_json::from_str::<PuzzleArchive>(herring).is_err());
+
+        let long = "a".repeat(crate::game::limits::MAX_TEXT_LEN + 1);
+        let json = format!(
+            r#"{{"puzzles": [{{"date": "2026-10-01", "puzzle": {{"sets": [
+                {{"theme": "{long}", "words": ["a", "b"]}},
+                {{"theme": "b", "words": ["c", "d"]}}
+            ]}}}}]}}"#
+        );
+        assert!(serde_json::from_str::<PuzzleArchive>(&json).is_err());
+
+        let labels = r#"{"puzzles": [{"date": "2026-10-01", "puzzle": {"sets": [
+            {"theme": "a", "words": ["a", "b"], "style": {"label": " Same "}},
+            {"theme": "b", "words": ["c", "d"], "style": {"label": "same"}}
+        ]}}]}"#;
+        let archive: PuzzleArchive = serde_json::from_str(labels).unwrap();
+        let puzzle = archive.number(1).unwrap().puzzle;
+        assert_eq!(puzzle.label(Color::Yellow), "yellow");
+        assert_eq!(puzzle.label(Color::Blue), "same");
+    }
+}
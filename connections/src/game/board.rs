@@ -1,34 +1,110 @@
 use super::color::Color;
 use super::puzzle::PuzzleRef;
 use super::ConnectionPuzzle;
-use crate::console_log;
 use crate::game::ConnectionSet;
 use rand::prelude::SliceRandom;
-use std::mem::MaybeUninit;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use std::ops::Index;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-#[allow(unused_imports)]
-use web_sys::console;
 
 #[derive(Debug)]
 pub struct Board {
     pub selection: Selection,
     matched_cards: MatchedCards,
     puzzle: ConnectionPuzzle,
-    order: [PuzzleRef; 16],
+    order: Vec<PuzzleRef>,
+    /// The solutions that still agree with every group matched so far, see
+    /// [`ConnectionPuzzle::group_of`]. Never empty.
+    live: Vec<usize>,
+    seed: u64,
+    shuffles: u64,
+}
+
+/// The parts of a board that change during a game
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BoardSnapshot {
+    selection: Selection,
+    matched_cards: MatchedCards,
+    order: Vec<PuzzleRef>,
+    live: Vec<usize>,
+    shuffles: u64,
+}
+
+impl BoardSnapshot {
+    /// Whether this could have come from a board for `puzzle`
+    pub fn fits(&self, puzzle: &ConnectionPuzzle) -> bool {
+        let cards = PuzzleRef::new_set(puzzle.num_groups(), puzzle.group_size());
+        let is_layout =
+            self.order.len() == cards.len() && cards.iter().all(|card| self.order.contains(card));
+        if !is_layout || self.matched_cards.matched.len() != puzzle.num_groups() {
+            return false;
+        }
+        let live_solutions = self.live.windows(2).all(|pair| pair[0] < pair[1])
+            && self
+                .live
+                .last()
+                .is_some_and(|&last| last < puzzle.num_solutions());
+        if !live_solutions {
+            return false;
+        }
+
+        self.is_selection_for(&self.selection, puzzle)
+            && !self.selection.iter().any(|card| {
+                self.matched_cards
+                    .contains(puzzle.group_of(self.live[0], card))
+            })
+    }
+
+    /// Whether `selection` only holds cards from this board, each at most once
+    pub fn is_selection_for(&self, selection: &Selection, puzzle: &ConnectionPuzzle) -> bool {
+        let unique = selection
+            .iter()
+            .enumerate()
+            .all(|(index, card)| !selection.iter().skip(index + 1).any(|other| other == card));
+        selection.capacity == puzzle.group_size()
+            && selection.len() <= selection.capacity
+            && unique
+            && selection.iter().all(|card| self.order.contains(&card))
+    }
+
+    pub fn num_matched(&self) -> usize {
+        self.matched_cards.num_matched()
+    }
 }
 
 impl Board {
-    pub fn get(&self, index: usize) -> Card {
+    pub fn get(&self, index: usize) -> Card<'_> {
         let card = self.order[index];
+        let color = self.group_of(card);
         Card {
-            color: card.color(),
+            color,
+            label: self.puzzle.label(color),
+            hex: self.puzzle.hex(color),
             word: self.card_word(card),
             theme: self.card_theme(card),
             state: self.card_state(card),
         }
     }
 
+    pub fn num_cards(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn num_groups(&self) -> usize {
+        self.puzzle.num_groups()
+    }
+
+    pub fn group_size(&self) -> usize {
+        self.puzzle.group_size()
+    }
+
+    pub fn puzzle(&self) -> &ConnectionPuzzle {
+        &self.puzzle
+    }
+
     pub fn get_word(&self, index: usize) -> &str {
         let card = self.order[index];
         self.card_word(card)
@@ -38,14 +114,16 @@ impl Board {
         self.puzzle.by_color(color)
     }
     pub fn reset(&mut self) {
+        self.puzzle.reseal();
         self.selection.clear();
         self.matched_cards.clear();
+        self.live = (0..self.puzzle.num_solutions()).collect();
         self.shuffle();
     }
 
     pub fn select(&mut self, index: usize) -> Result<usize, SelectionFailiure> {
         let reference = self.order[index];
-        if self.matched_cards.contains(reference.color()) {
+        if self.matched_cards.contains(self.group_of(reference)) {
             CardState::Matched
         } else {
             self.selection.toggle(reference)?.into()
@@ -59,72 +137,178 @@ impl Board {
 
     pub const fn empty() -> Self {
         let puzzle = ConnectionPuzzle::empty();
-        let selection = Selection::new();
+        let selection = Selection::new(0);
         let matched_cards = MatchedCards::new();
-        let order = PuzzleRef::new_set();
+        let order = Vec::new();
         Self {
             puzzle,
             selection,
             matched_cards,
             order,
+            live: Vec::new(),
+            seed: 0,
+            shuffles: 0,
         }
     }
 
-    pub fn encode(&self) -> String {
-        self.puzzle.encode()
-    }
-
     pub fn test_selection(&mut self) -> Result<Color, SelectionFailiure> {
-        if self.selection.len() != 4 {
+        let group_size = self.group_size();
+        if self.selection.len() != group_size {
             return Err(SelectionFailiure::NotEnough);
         }
-        let color = self.selection[0].color();
-
-        let matches = self.selection.iter().filter(|x| x.color() == color).count();
-        match matches {
-            4 => {
-                self.move_matched();
-                self.matched_cards.mark_match(color);
-                self.selection.clear();
+        if self.puzzle.is_sealed() {
+            return self.test_sealed();
+        }
+        // judge the guess by whichever group most of it belongs to, in whichever solution
+        // it comes closest to
+        let closest = self
+            .live
+            .iter()
+            .map(|&solution| {
+                let group_of = |card| self.puzzle.group_of(solution, card);
+                self.selection
+                    .iter()
+                    .map(|card| {
+                        let color = group_of(card);
+                        let matches = self.selection.iter().filter(|&x| group_of(x) == color);
+                        (color, matches.count())
+                    })
+                    .max_by_key(|&(_, matches)| matches)
+                    .expect("selection is full")
+            })
+            .max_by_key(|&(_, matches)| matches)
+            .expect("a solution is always live");
+
+        match closest {
+            (color, matches) if matches == group_size => {
+                let selection = self.selection.clone();
+                self.live.retain(|&solution| {
+                    selection
+                        .iter()
+                        .all(|card| self.puzzle.group_of(solution, card) == color)
+                });
+                self.mark_matched(color);
                 Ok(color)
             }
-            3 => Err(SelectionFailiure::OneAway),
+            (_, matches) if matches + 1 == group_size => Err(SelectionFailiure::OneAway),
             _ => Err(SelectionFailiure::Mismatch),
         }
     }
 
-    fn move_matched(&mut self) {
-        console_log!("move matched");
-        let mut top_of_board = self.matched_cards.num_matched() * 4;
-        for reference in self.selection.iter() {
+    /// A sealed puzzle can't say which group a card is in, only whether a guess is exactly
+    /// a group. It's one away if swapping a single card for one still on the board makes it one.
+    fn test_sealed(&mut self) -> Result<Color, SelectionFailiure> {
+        let guess: Vec<PuzzleRef> = self.selection.iter().collect();
+        if let Some(color) = self.puzzle.unseal(&guess) {
+            self.mark_matched(color);
+            return Ok(color);
+        }
+        let others: Vec<PuzzleRef> = self
+            .order
+            .iter()
+            .copied()
+            .filter(|&card| !self.selection.contains(card) && !self.is_matched(self.group_of(card)))
+            .collect();
+        let one_away = (0..guess.len()).any(|index| {
+            others.iter().any(|&other| {
+                let mut near = guess.clone();
+                near[index] = other;
+                self.puzzle.is_sealed_group(&near)
+            })
+        });
+        if one_away {
+            Err(SelectionFailiure::OneAway)
+        } else {
+            Err(SelectionFailiure::Mismatch)
+        }
+    }
+
+    /// A sealed puzzle finds its groups as they're guessed, which isn't part of a snapshot, so
+    /// going back to one seals the puzzle again and finds the groups its `guesses` did
+    pub fn replay_seal(&mut self, guesses: &[Selection]) {
+        if !self.puzzle.is_sealed() {
+            return;
+        }
+        self.puzzle.reseal();
+        for guess in guesses {
+            self.puzzle.unseal(&guess.iter().collect::<Vec<_>>());
+        }
+    }
+
+    /// Which group `card` is in, going by the solutions still live. Until a card's group is
+    /// matched this is only one of the groups it could turn out to be in.
+    pub fn group_of(&self, card: PuzzleRef) -> Color {
+        self.puzzle.group_of(self.live[0], card)
+    }
+
+    /// The cards of a group, in the order they're written in the puzzle
+    pub fn group(&self, color: Color) -> impl Iterator<Item = PuzzleRef> + use<'_> {
+        PuzzleRef::new_set(self.num_groups(), self.group_size())
+            .into_iter()
+            .filter(move |&card| self.group_of(card) == color)
+    }
+
+    pub fn is_matched(&self, color: Color) -> bool {
+        self.matched_cards.contains(color)
+    }
+
+    /// Moves a group to the top of the board as solved, whether it was guessed or given away.
+    /// Solutions that put different cards in the group stop being live.
+    pub fn mark_matched(&mut self, color: Color) {
+        let top_of_board = self.matched_cards.num_matched() * self.group_size();
+        let group: Vec<PuzzleRef> = self.group(color).collect();
+        self.live.retain(|&solution| {
+            group
+                .iter()
+                .all(|&card| self.puzzle.group_of(solution, card) == color)
+        });
+        for (offset, reference) in group.into_iter().enumerate() {
             let index = self.order.iter().position(|&x| x == reference).unwrap();
-            self.order.swap(top_of_board, index);
-            top_of_board += 1;
+            self.order.swap(top_of_board + offset, index);
         }
+        self.matched_cards.mark_match(color);
+        self.selection.clear();
     }
 
     pub fn shuffle(&mut self) {
-        let starting_point = self.matched_cards.num_matched() * 4;
-        let mut rng = rand::thread_rng();
+        let starting_point = self.matched_cards.num_matched() * self.group_size();
+        let mut rng = self.next_rng();
         self.order[starting_point..].shuffle(&mut rng);
     }
 
-    pub fn new(puzzle: ConnectionPuzzle) -> Self {
-        let selection = Selection::new();
-        let matched_cards = MatchedCards::default();
-        let mut rng = rand::thread_rng();
-        let mut order = PuzzleRef::new_set();
-        order.shuffle(&mut rng);
-        Self {
+    /// Every shuffle draws from its own stream of the seed, so a board's layout only depends
+    /// on the seed and how many times it's been shuffled
+    fn next_rng(&mut self) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(self.shuffles);
+        self.shuffles += 1;
+        rng
+    }
+
+    pub fn new(puzzle: ConnectionPuzzle, seed: u64) -> Self {
+        let selection = Selection::new(puzzle.group_size());
+        let matched_cards = MatchedCards::with_groups(puzzle.num_groups());
+        let order = PuzzleRef::new_set(puzzle.num_groups(), puzzle.group_size());
+        let live = (0..puzzle.num_solutions()).collect();
+        let mut board = Self {
             puzzle,
             selection,
             matched_cards,
             order,
-        }
+            live,
+            seed,
+            shuffles: 0,
+        };
+        board.shuffle();
+        board
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
     fn card_state(&self, card: PuzzleRef) -> CardState {
-        if self.matched_cards.contains(card.color()) {
+        if self.matched_cards.contains(self.group_of(card)) {
             CardState::Matched
         } else if self.selection.contains(card) {
             CardState::Selected
@@ -134,7 +318,7 @@ impl Board {
     }
 
     fn card_theme(&self, card: PuzzleRef) -> &str {
-        self.puzzle.by_color(card.color()).theme_ref()
+        self.puzzle.by_color(self.group_of(card)).theme_ref()
     }
 
     fn card_word(&self, card: PuzzleRef) -> &str {
@@ -142,17 +326,35 @@ impl Board {
     }
 
     pub fn matched_set_text(&self, color: Color) -> (&str, String) {
-        let set = self.puzzle.by_color(color);
-        (set.theme_ref(), set.words())
+        let words: Vec<&str> = self.group(color).map(|card| self.card_word(card)).collect();
+        (self.puzzle.by_color(color).theme_ref(), words.join(", "))
     }
 
     pub fn swap(&mut self, a: usize, b: usize) {
         self.order.swap(a, b);
     }
+
+    pub fn snapshot(&self) -> BoardSnapshot {
+        BoardSnapshot {
+            selection: self.selection.clone(),
+            matched_cards: self.matched_cards.clone(),
+            order: self.order.clone(),
+            live: self.live.clone(),
+            shuffles: self.shuffles,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: BoardSnapshot) {
+        self.selection = snapshot.selection;
+        self.matched_cards = snapshot.matched_cards;
+        self.order = snapshot.order;
+        self.live = snapshot.live;
+        self.shuffles = snapshot.shuffles;
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
-#[wasm_bindgen]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub enum CardState {
     Selected,
     Normal,
@@ -167,22 +369,31 @@ impl From<SelectState> for CardState {
         }
     }
 }
+#[cfg(feature = "wasm")]
 use js_sys::JsString;
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub struct OwnedCard {
     #[wasm_bindgen(getter_with_clone)]
     pub word: JsString,
     #[wasm_bindgen(getter_with_clone)]
     pub theme: JsString,
+    #[wasm_bindgen(getter_with_clone)]
+    pub label: JsString,
+    #[wasm_bindgen(getter_with_clone)]
+    pub hex: JsString,
     pub color: Color,
     pub state: CardState,
 }
 
+#[cfg(feature = "wasm")]
 impl From<Card<'_>> for OwnedCard {
     fn from(card: Card<'_>) -> Self {
         Self {
             word: card.word.into(),
             theme: card.theme.into(),
+            label: card.label.into(),
+            hex: card.hex.into(),
             color: card.color,
             state: card.state,
         }
@@ -191,6 +402,10 @@ impl From<Card<'_>> for OwnedCard {
 
 pub struct Card<'a> {
     pub color: Color,
+    /// The group's label, see [`ConnectionPuzzle::label`]
+    pub label: &'a str,
+    /// The group's `#rrggbb` color, for painting matched cards
+    pub hex: &'a str,
     pub word: &'a str,
     pub theme: &'a str,
     pub state: CardState,
@@ -208,12 +423,8 @@ impl<'a> Card<'a> {
         match self.state {
             CardState::Normal => "card",
             CardState::Selected => "selected",
-            CardState::Matched => match self.color {
-                Color::Yellow => "matched_yellow",
-                Color::Green => "matched_green",
-                Color::Blue => "matched_blue",
-                Color::Purple => "matched_purple",
-            },
+            // groups can be any color, so matched cards are painted with `hex` instead
+            CardState::Matched => "matched",
         }
     }
 }
@@ -223,109 +434,76 @@ pub enum SelectState {
     Normal,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SelectionFailiure {
     Mismatch,
     NotEnough,
     OneAway,
 }
 
-#[derive(Debug, Default)]
+/// Which groups have been found, indexed by color
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 struct MatchedCards {
-    yellow: bool,
-    blue: bool,
-    purple: bool,
-    green: bool,
+    matched: Vec<bool>,
 }
 
 impl MatchedCards {
     fn num_matched(&self) -> usize {
-        let mut num = 0;
-        if self.yellow {
-            num += 1
-        };
-        if self.blue {
-            num += 1
-        };
-        if self.purple {
-            num += 1
-        };
-        if self.green {
-            num += 1
-        };
-        num
+        self.matched.iter().filter(|&&matched| matched).count()
     }
 
     fn contains(&self, color: Color) -> bool {
-        *self.by_color(color)
+        self.matched[color.index()]
     }
 
     fn clear(&mut self) {
-        self.yellow = false;
-        self.blue = false;
-        self.purple = false;
-        self.green = false;
+        self.matched.fill(false);
     }
 
     fn mark_match(&mut self, color: Color) {
-        *self.by_color_mut(color) = true;
+        self.matched[color.index()] = true;
     }
 
-    fn by_color(&self, color: Color) -> &bool {
-        match color {
-            Color::Yellow => &self.yellow,
-            Color::Blue => &self.blue,
-            Color::Purple => &self.purple,
-            Color::Green => &self.green,
-        }
-    }
-
-    fn by_color_mut(&mut self, color: Color) -> &mut bool {
-        match color {
-            Color::Yellow => &mut self.yellow,
-            Color::Blue => &mut self.blue,
-            Color::Purple => &mut self.purple,
-            Color::Green => &mut self.green,
+    fn with_groups(num_groups: usize) -> Self {
+        Self {
+            matched: vec![false; num_groups],
         }
     }
 
     const fn new() -> Self {
         Self {
-            yellow: false,
-            blue: false,
-            purple: false,
-            green: false,
+            matched: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Selection {
-    selection: [MaybeUninit<PuzzleRef>; 4],
-    len: SelectionSize,
+    selection: Vec<PuzzleRef>,
+    capacity: usize,
 }
 
 impl Selection {
-    const fn new() -> Self {
-        let selection = [MaybeUninit::uninit(); 4];
-        let len = SelectionSize::Empty;
-        Self { selection, len }
+    const fn new(capacity: usize) -> Self {
+        let selection = Vec::new();
+        Self {
+            selection,
+            capacity,
+        }
     }
 
     fn toggle(&mut self, card: PuzzleRef) -> Result<SelectState, SelectionFailiure> {
         let index = self.iter().position(|selected_card| selected_card == card);
         match index {
             Some(index) => {
-                console::log_1(&"found, removing".into());
                 self.remove(index);
                 Ok(SelectState::Normal)
             }
             None => {
                 if self.push(card).is_some() {
-                    console::log_1(&"not found, adding".into());
                     Ok(SelectState::Selected)
                 } else {
-                    console::log_1(&"Selection Full".into());
                     Err(SelectionFailiure::NotEnough)
                 }
             }
@@ -333,68 +511,43 @@ impl Selection {
     }
 
     fn remove(&mut self, index: usize) {
-        let end_index = self.len() - 1;
-        self.selection.swap(index, end_index);
-        console::log_1(&format!("before remove len is {}", self.len()).into());
-        self.len = match self.len {
-            SelectionSize::Empty => unreachable!(),
-            SelectionSize::One => SelectionSize::Empty,
-            SelectionSize::Two => SelectionSize::One,
-            SelectionSize::Three => SelectionSize::Two,
-            SelectionSize::Four => SelectionSize::Three,
-        };
-        console::log_1(&format!("after remove len is {}", self.len()).into());
+        self.selection.swap_remove(index);
     }
 
     fn push(&mut self, card: PuzzleRef) -> Option<usize> {
-        if self.len() == 4 {
+        if self.len() == self.capacity {
             return None;
         }
 
-        self.selection[self.len()].write(card);
-        match self.len {
-            SelectionSize::Empty => self.len = SelectionSize::One,
-            SelectionSize::One => self.len = SelectionSize::Two,
-            SelectionSize::Two => self.len = SelectionSize::Three,
-            SelectionSize::Three => self.len = SelectionSize::Four,
-            SelectionSize::Four => panic!("pushed too many to selection len"),
-        };
+        self.selection.push(card);
         Some(self.len())
     }
 
     fn clear(&mut self) {
-        self.len = SelectionSize::Empty;
+        self.selection.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.selection.len()
     }
 
-    pub const fn len(&self) -> usize {
-        self.len as usize
+    pub fn is_empty(&self) -> bool {
+        self.selection.is_empty()
     }
 
     pub fn contains(&self, target_card: PuzzleRef) -> bool {
-        for card in self.iter() {
-            if card == target_card {
-                return true;
-            }
-        }
-        false
+        self.selection.contains(&target_card)
     }
 
     pub fn iter(&self) -> impl Iterator<Item = PuzzleRef> + use<'_> {
-        let slice = &self.selection[0..self.len()];
-        let iter = slice.iter().map(|index| unsafe { index.assume_init() });
-        iter
+        self.selection.iter().copied()
     }
 }
 
 impl PartialEq for Selection {
     fn eq(&self, other: &Self) -> bool {
-        // kinda inneficient but both arrays are length 4 so it doesnt matter
-        for card in self.iter() {
-            if !other.contains(card) {
-                return false;
-            }
-        }
-        true
+        // selections are tiny so checking every card against the other is fine
+        self.len() == other.len() && self.iter().all(|card| other.contains(card))
     }
 }
 
@@ -402,25 +555,6 @@ impl Index<usize> for Selection {
     type Output = PuzzleRef;
 
     fn index(&self, index: usize) -> &Self::Output {
-        assert!(index < (self.len as usize));
-        unsafe { self.selection[index].assume_init_ref() }
+        &self.selection[index]
     }
 }
-
-#[derive(Debug, Copy, Clone)]
-#[repr(usize)]
-enum SelectionSize {
-    Empty = 0,
-    One = 1,
-    Two = 2,
-    Three = 3,
-    Four = 4,
-}
-
-/*
-struct SelectedCards{
-    cards:[]
-}
-*/
-
-impl Board {}
@@ -0,0 +1,276 @@
+use super::puzzle::TranscodingError;
+use base64::{alphabet, engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+
+/// Goes between a code's checksum and the code. It isn't in the base64 alphabet, so codes
+/// from before checksums, which never have one, still read as they are.
+pub const SEPARATOR: char = '~';
+
+/// A prime above the length of any code, so a single typo's position can be worked out
+/// from how far off the two sums are
+const MODULUS: u64 = 65521;
+
+/// The checksum goes in front, so a code cut short still has it. Base64 writes its 9 bytes
+/// in 12 characters.
+pub fn add(code: &str) -> String {
+    let sums = Sums::of(code);
+    let mut check = Vec::with_capacity(9);
+    for field in [sums.len, sums.plain, sums.weighted] {
+        check.extend((field as u16).to_le_bytes());
+    }
+    check.extend(header_crc(&check));
+    format!("{}{SEPARATOR}{code}", URL_SAFE_NO_PAD.encode(check))
+}
+
+/// Hands back the code without its checksum once it's been checked. Positions in errors
+/// count characters from the start of `text`, starting at 1.
+pub fn check(text: &str) -> Result<&str, TranscodingError> {
+    let Some((check, code)) = text.split_once(SEPARATOR) else {
+        return Ok(text);
+    };
+    let expected = read_check(check).ok_or(TranscodingError::Mistyped)?;
+    let actual = Sums::of(code);
+    let offset = check.chars().count() + 1;
+    if actual.len != expected.len {
+        return Err(wrong_length(code, &expected, &actual, offset));
+    }
+    let plain = (actual.plain + MODULUS - expected.plain) % MODULUS;
+    let weighted = (actual.weighted + MODULUS - expected.weighted) % MODULUS;
+    if (plain == 0) != (weighted == 0) {
+        return Err(TranscodingError::Mistyped);
+    }
+    if plain == 0 {
+        return Ok(code);
+    }
+
+    // one character off by `plain` at position `n` throws the weighted sum off by `n * plain`
+    let position = weighted * inverse(plain) % MODULUS;
+    let typo = position
+        .checked_sub(1)
+        .and_then(|index| usize::try_from(index).ok())
+        .and_then(|index| code.chars().nth(index))
+        .ok_or(TranscodingError::Mistyped)?;
+    let intended = (value(typo) + MODULUS - plain) % MODULUS;
+    if is_base64(intended) {
+        Err(TranscodingError::Typo {
+            position: offset + position as usize,
+        })
+    } else {
+        Err(TranscodingError::Mistyped)
+    }
+}
+
+/// A code one character short or long is searched for the character that was dropped or
+/// added. Any shorter and it's taken to be cut short, which is how links usually lose
+/// characters, and a code that's cut short is only ever missing its end.
+fn wrong_length(code: &str, expected: &Sums, actual: &Sums, offset: usize) -> TranscodingError {
+    let values: Vec<u64> = code.chars().map(value).collect();
+    let truncated = TranscodingError::Truncated {
+        expected: offset + expected.len as usize,
+        found: offset + actual.len as usize,
+    };
+    if actual.len + 1 == expected.len {
+        // dropping a character at `n` takes `n` times it off the weighted sum, and
+        // everything after it moves back one, taking each of them off once more
+        let missing = (expected.plain + MODULUS - actual.plain) % MODULUS;
+        let weighted = (expected.weighted + MODULUS - actual.weighted) % MODULUS;
+        // the end's checked first, since a repeated last character could be either
+        if expected.len * missing % MODULUS == weighted {
+            return truncated;
+        }
+        let mut after = actual.plain;
+        let mut positions = Vec::new();
+        for (position, &next) in (1..).zip(&values) {
+            if (position * missing + after) % MODULUS == weighted {
+                positions.push(position);
+            }
+            after = (after + MODULUS - next) % MODULUS;
+        }
+        return match only_one(&values, missing, &positions) {
+            Some(position) if is_base64(missing) => TranscodingError::Missing {
+                position: offset + position as usize,
+            },
+            _ => TranscodingError::Mistyped,
+        };
+    }
+    if actual.len < expected.len {
+        return truncated;
+    }
+    if actual.len == expected.len + 1 {
+        // the other way around, for a character that shouldn't be there
+        let extra = (actual.plain + MODULUS - expected.plain) % MODULUS;
+        let weighted = (actual.weighted + MODULUS - expected.weighted) % MODULUS;
+        let mut after = actual.plain;
+        let mut positions = Vec::new();
+        for (position, &character) in (1..).zip(&values) {
+            after = (after + MODULUS - character) % MODULUS;
+            if character == extra && (position * extra + after) % MODULUS == weighted {
+                positions.push(position);
+            }
+        }
+        if let Some(position) = only_one(&values, extra, &positions) {
+            return TranscodingError::Extra {
+                position: offset + position as usize,
+            };
+        }
+    }
+    TranscodingError::Mistyped
+}
+
+/// The sums can't tell apart every place a character could have been dropped or added, so
+/// one is only pointed to if it's the only one. Places in a run of that same character are
+/// all the same place, and the run's start stands for them.
+fn only_one(values: &[u64], character: u64, positions: &[u64]) -> Option<u64> {
+    let mut starts = positions
+        .iter()
+        .copied()
+        .filter(|&position| position == 1 || values.get(position as usize - 2) != Some(&character));
+    let start = starts.next()?;
+    starts.next().is_none().then_some(start)
+}
+
+fn is_base64(value: u64) -> bool {
+    u8::try_from(value)
+        .is_ok_and(|byte| byte == b'=' || alphabet::URL_SAFE.as_str().as_bytes().contains(&byte))
+}
+
+struct Sums {
+    len: u64,
+    plain: u64,
+    weighted: u64,
+}
+
+impl Sums {
+    fn of(code: &str) -> Self {
+        let mut sums = Self {
+            len: 0,
+            plain: 0,
+            weighted: 0,
+        };
+        for character in code.chars() {
+            sums.len += 1;
+            sums.plain = (sums.plain + value(character)) % MODULUS;
+            sums.weighted = (sums.weighted + sums.len % MODULUS * value(character)) % MODULUS;
+        }
+        sums.len %= MODULUS;
+        sums
+    }
+}
+
+fn read_check(check: &str) -> Option<Sums> {
+    let bytes = URL_SAFE_NO_PAD.decode(check).ok()?;
+    let (fields, crc) = bytes.split_at_checked(6)?;
+    if crc != header_crc(fields) {
+        return None;
+    }
+    let field =
+        |index: usize| u64::from(u16::from_le_bytes([fields[index], fields[index + 1]])) % MODULUS;
+    Some(Sums {
+        len: field(0),
+        plain: field(2),
+        weighted: field(4),
+    })
+}
+
+/// Guards the sums themselves, since a typo in them would otherwise point somewhere else
+fn header_crc(fields: &[u8]) -> [u8; 3] {
+    let mut crc = flate2::Crc::new();
+    crc.update(fields);
+    let [a, b, c, _] = crc.sum().to_le_bytes();
+    [a, b, c]
+}
+
+fn value(character: char) -> u64 {
+    u64::from(character) % MODULUS
+}
+
+/// Fermat's little theorem, since the modulus is prime
+fn inverse(value: u64) -> u64 {
+    let (mut base, mut exponent, mut result) = (value, MODULUS - 2, 1);
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % MODULUS;
+        }
+        base = base * base % MODULUS;
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "Q04CXY5BCsIwEEUxCr1Ez5BVc4cuvIFuUzO2gZCETDZeSS_ZBLpwGPgM";
+
+    #[test]
+    fn checks_out() {
+        assert_eq!(check(&add(CODE)), Ok(CODE));
+        assert_eq!(check(CODE), Ok(CODE));
+    }
+
+    #[test]
+    fn finds_a_typo() {
+        let text = add(CODE);
+        let position = text.len() - 10;
+        let mut typo = text.clone().into_bytes();
+        typo[position - 1] = if typo[position - 1] == b'A' {
+            b'B'
+        } else {
+            b'A'
+        };
+        let typo = String::from_utf8(typo).unwrap();
+        assert_eq!(check(&typo), Err(TranscodingError::Typo { position }));
+    }
+
+    #[test]
+    fn finds_a_code_cut_short() {
+        let text = add(CODE);
+        assert_eq!(
+            check(&text[..text.len() - 5]),
+            Err(TranscodingError::Truncated {
+                expected: text.len(),
+                found: text.len() - 5,
+            })
+        );
+    }
+
+    #[test]
+    fn finds_a_dropped_character() {
+        let text = add(CODE);
+        let position = text.len() - 20;
+        let mut dropped = text.clone();
+        dropped.remove(position - 1);
+        assert_eq!(check(&dropped), Err(TranscodingError::Missing { position }));
+    }
+
+    #[test]
+    fn finds_an_extra_character() {
+        let text = add(CODE);
+        let position = text.len() - 20;
+        let mut extra = text.clone();
+        extra.insert(position - 1, '_');
+        assert_eq!(check(&extra), Err(TranscodingError::Extra { position }));
+    }
+
+    #[test]
+    fn losing_the_last_character_is_cut_short() {
+        let text = add(CODE);
+        assert_eq!(
+            check(&text[..text.len() - 1]),
+            Err(TranscodingError::Truncated {
+                expected: text.len(),
+                found: text.len() - 1,
+            })
+        );
+    }
+
+    #[test]
+    fn a_swap_is_mistyped() {
+        let text = add(CODE);
+        let mut swapped = text.clone().into_bytes();
+        let end = swapped.len();
+        swapped.swap(end - 3, end - 8);
+        let swapped = String::from_utf8(swapped).unwrap();
+        assert_eq!(check(&swapped), Err(TranscodingError::Mistyped));
+    }
+}
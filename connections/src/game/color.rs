@@ -1,22 +1,28 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+
+/// Every group in a puzzle gets its own color, so this is also the most groups a puzzle can have.
+pub const MAX_GROUPS: usize = 6;
+
+/// Which group is which, by its position in the puzzle. Games, saves, logs and the site all
+/// name groups this way, and a puzzle's own labels and hex colors only change how a group is
+/// shown, so there are never more than [`MAX_GROUPS`] groups.
 #[repr(u8)]
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub enum Color {
     Yellow = 0,
     Blue = 1,
     Purple = 2,
     Green = 3,
+    Red = 4,
+    Orange = 5,
 }
 
 impl AsRef<str> for Color {
     fn as_ref(&self) -> &'static str {
-        match self {
-            Self::Yellow => "yellow",
-            Self::Blue => "blue",
-            Self::Purple => "purple",
-            Self::Green => "green",
-        }
+        self.name()
     }
 }
 
@@ -29,47 +35,133 @@ impl TryFrom<&str> for Color {
             "blue" => Ok(Self::Blue),
             "green" => Ok(Self::Green),
             "purple" => Ok(Self::Purple),
+            "red" => Ok(Self::Red),
+            "orange" => Ok(Self::Orange),
             _ => Err(()),
         }
     }
 }
 
+#[cfg(feature = "wasm")]
 impl From<Color> for js_sys::JsString {
     fn from(val: Color) -> Self {
-        match val {
-            Color::Yellow => "yellow".into(),
-            Color::Blue => "blue".into(),
-            Color::Purple => "purple".into(),
-            Color::Green => "green".into(),
+        AsRef::<str>::as_ref(&val).into()
+    }
+}
+
+/// Iterates over the colors of the first `len` groups, in group order
+pub struct ColorIter {
+    next: u8,
+    len: u8,
+}
+
+impl ColorIter {
+    pub fn new(len: usize) -> Self {
+        assert!(len <= MAX_GROUPS);
+        Self {
+            next: 0,
+            len: len as u8,
         }
     }
 }
 
-#[derive(Default)]
-pub struct ColorIter(Option<Color>);
+impl Default for ColorIter {
+    fn default() -> Self {
+        Self::new(MAX_GROUPS)
+    }
+}
 
 impl Iterator for ColorIter {
     type Item = Color;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0 {
-            Some(Color::Green) => None,
-            None => Some(Color::Yellow),
-            Some(Color::Yellow) => Some(Color::Blue),
-            Some(Color::Blue) => Some(Color::Purple),
-            Some(Color::Purple) => Some(Color::Green),
+        if self.next == self.len {
+            return None;
         }
+        let color = Color::from_int(self.next)?;
+        self.next += 1;
+        Some(color)
     }
 }
 
 impl Color {
-    pub const fn from_int(int: u8) -> Self {
+    /// The color of the group at `int`, if a puzzle can have a group there
+    pub const fn from_int(int: u8) -> Option<Self> {
         match int {
-            0 => Color::Yellow,
-            1 => Color::Blue,
-            2 => Color::Purple,
-            3 => Color::Green,
-            _ => unreachable!(),
+            0 => Some(Color::Yellow),
+            1 => Some(Color::Blue),
+            2 => Some(Color::Purple),
+            3 => Some(Color::Green),
+            4 => Some(Color::Red),
+            5 => Some(Color::Orange),
+            _ => None,
+        }
+    }
+
+    /// The position of this color's group in its puzzle
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Yellow => "yellow",
+            Self::Blue => "blue",
+            Self::Purple => "purple",
+            Self::Green => "green",
+            Self::Red => "red",
+            Self::Orange => "orange",
+        }
+    }
+
+    /// The NYT palette, plus two more for bigger puzzles
+    pub const fn hex(self) -> &'static str {
+        match self {
+            Self::Yellow => "#f9df6d",
+            Self::Blue => "#b0c4ef",
+            Self::Purple => "#ba81c5",
+            Self::Green => "#a0c35a",
+            Self::Red => "#e58e7f",
+            Self::Orange => "#f2b46b",
+        }
+    }
+
+    /// Where the color falls in difficulty when a puzzle doesn't rank its groups, going by
+    /// the NYT's yellow, green, blue, purple
+    pub const fn default_rank(self) -> u8 {
+        match self {
+            Self::Yellow => 0,
+            Self::Green => 1,
+            Self::Blue => 2,
+            Self::Purple => 3,
+            Self::Red => 4,
+            Self::Orange => 5,
+        }
+    }
+
+    /// The square shown for this color in a shared result
+    pub const fn emoji(self) -> &'static str {
+        match self {
+            Self::Yellow => "🟨",
+            Self::Blue => "🟦",
+            Self::Purple => "🟪",
+            Self::Green => "🟩",
+            Self::Red => "🟥",
+            Self::Orange => "🟧",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn there_are_only_as_many_colors_as_groups() {
+        assert_eq!(ColorIter::default().count(), MAX_GROUPS);
+        assert_eq!(Color::from_int(MAX_GROUPS as u8), None);
+        for (index, color) in ColorIter::default().enumerate() {
+            assert_eq!(color.index(), index);
         }
     }
 }
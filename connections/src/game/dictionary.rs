@@ -0,0 +1,71 @@
+use super::limits;
+use super::puzzle::TranscodingError;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::sync::OnceLock;
+
+/// Words and theme phrases common in puzzles, each with the length postcard puts in front of
+/// it, made by `codec train` from `dictionary/corpus.txt`. Codes are compressed against it,
+/// so a retrained dictionary needs a new code version.
+pub const DICTIONARY: &[u8] = include_bytes!("puzzles.dict");
+
+/// The most a dictionary can hold and still be reached from anywhere in a puzzle, since
+/// deflate only looks 32 KiB back
+pub const MAX_DICTIONARY_LEN: usize = 16 * 1024;
+
+/// Raw deflate primed with [`DICTIONARY`], so words in it cost a back reference instead of
+/// their letters. There's no gzip header or trailer either.
+pub fn compress(postcard_bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(DICTIONARY).unwrap();
+    // a sync flush ends the dictionary on a byte boundary, so everything after it can be
+    // cut off and glued back on when decoding
+    encoder.flush().unwrap();
+    let primed = encoder.get_ref().len();
+    encoder.write_all(postcard_bytes).unwrap();
+    let mut compressed_bytes = encoder.finish().unwrap();
+    compressed_bytes.drain(..primed);
+    compressed_bytes
+}
+
+pub fn decompress(compressed_bytes: &[u8]) -> Result<Vec<u8>, TranscodingError> {
+    let decoder = DeflateDecoder::new(primed().chain(compressed_bytes));
+    let bytes = limits::decompressed(decoder, DICTIONARY.len())?;
+    match bytes.strip_prefix(DICTIONARY) {
+        Some(postcard_bytes) => Ok(postcard_bytes.to_vec()),
+        None => Err(TranscodingError::Decompress),
+    }
+}
+
+/// The dictionary compressed up to the sync flush, which is what every code continues from.
+/// Any compression of it works as long as it ends on that flush.
+fn primed() -> &'static [u8] {
+    static PRIMED: OnceLock<Vec<u8>> = OnceLock::new();
+    PRIMED.get_or_init(|| {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(DICTIONARY).unwrap();
+        encoder.flush().unwrap();
+        encoder.get_ref().clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::ConnectionPuzzle;
+
+    #[test]
+    fn round_trips() {
+        let postcard_bytes = ConnectionPuzzle::default().serialize();
+        let compressed_bytes = compress(&postcard_bytes);
+        assert!(compressed_bytes.len() < postcard_bytes.len());
+        assert_eq!(decompress(&compressed_bytes).unwrap(), postcard_bytes);
+    }
+
+    #[test]
+    fn round_trips_nothing() {
+        assert_eq!(decompress(&compress(&[])).unwrap(), Vec::<u8>::new());
+    }
+}
@@ -0,0 +1,79 @@
+use super::puzzle::TranscodingError;
+
+/// Every code made since codes were versioned starts with these, and then the version of
+/// the layout inside
+pub const MAGIC: [u8; 2] = *b"CN";
+pub const CURRENT_VERSION: u8 = 2;
+/// Codes locked behind a passphrase, which plain codes don't move up to
+pub const LOCKED_VERSION: u8 = 3;
+/// Codes that keep their groups hidden until they're guessed
+pub const SEALED_VERSION: u8 = 4;
+/// Codes with a whole pack of puzzles in them
+pub const PACK_VERSION: u8 = 5;
+/// The newest version there is, whatever it's for
+pub const LATEST_VERSION: u8 = PACK_VERSION;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CodeVersion {
+    /// Codes from before versioning, which are gzip from the first byte. They're in the
+    /// version 1 layout, or the four fixed groups of the first codes.
+    Unversioned,
+    /// Gzip
+    V1,
+    /// Deflate against the built-in dictionary, with the same layout as version 1
+    V2,
+    /// Version 2 encrypted under a passphrase, see [`super::lock`]
+    V3,
+    /// The words in the open and each group sealed, see [`super::seal`]
+    V4,
+    /// Several puzzles of version 2 or 4 compressed together, see [`super::pack`]
+    V5,
+}
+
+impl CodeVersion {
+    pub const fn number(self) -> Option<u8> {
+        match self {
+            Self::Unversioned => None,
+            Self::V1 => Some(1),
+            Self::V2 => Some(2),
+            Self::V3 => Some(3),
+            Self::V4 => Some(4),
+            Self::V5 => Some(5),
+        }
+    }
+
+    pub fn from_number(number: u8) -> Result<Self, TranscodingError> {
+        match number {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            3 => Ok(Self::V3),
+            4 => Ok(Self::V4),
+            5 => Ok(Self::V5),
+            number => Err(TranscodingError::UnsupportedVersion(number)),
+        }
+    }
+}
+
+pub const fn header(version: u8) -> [u8; 3] {
+    [MAGIC[0], MAGIC[1], version]
+}
+
+/// Puts `version` in front of a compressed puzzle
+pub fn seal(version: u8, compressed_bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 1 + compressed_bytes.len());
+    bytes.extend(header(version));
+    bytes.extend(compressed_bytes);
+    bytes
+}
+
+/// Splits a code's bytes into its version and the compressed puzzle after it
+pub fn open(bytes: &[u8]) -> Result<(CodeVersion, &[u8]), TranscodingError> {
+    match bytes {
+        [m0, m1, version, rest @ ..] if [*m0, *m1] == MAGIC => {
+            Ok((CodeVersion::from_number(*version)?, rest))
+        }
+        // codes from before versioning start with gzip's 1f 8b
+        [0x1f, 0x8b, ..] => Ok((CodeVersion::Unversioned, bytes)),
+        _ => Err(TranscodingError::NotACode),
+    }
+}
@@ -0,0 +1,53 @@
+use super::color::Color;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A word the author put there to look like it belongs to another group
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RedHerring {
+    pub word_index: usize,
+    /// The group the word is meant to be mistaken for
+    pub decoy: Color,
+}
+
+/// A red herring a guess fell for, written out so it can be shown once the guess is judged
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trap {
+    word: String,
+    decoy: Color,
+    decoy_theme: String,
+}
+
+impl Trap {
+    pub(super) fn new(word: &str, decoy: Color, decoy_theme: &str) -> Self {
+        Self {
+            word: word.into(),
+            decoy,
+            decoy_theme: decoy_theme.into(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Trap {
+    pub fn word(&self) -> String {
+        self.word.clone()
+    }
+
+    pub fn decoy(&self) -> Color {
+        self.decoy
+    }
+
+    pub fn decoy_theme(&self) -> String {
+        self.decoy_theme.clone()
+    }
+
+    pub fn message(&self) -> String {
+        format!(
+            "{} looked like it went with {}",
+            self.word, self.decoy_theme
+        )
+    }
+}
@@ -0,0 +1,129 @@
+use super::color::Color;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The score a game starts with, before any hints are paid for
+pub const MAX_SCORE: u8 = 100;
+
+/// What taking a hint costs. Mistakes count toward the mistake limit like a wrong guess, and
+/// points come off the score.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct HintCost {
+    mistakes: u8,
+    points: u8,
+}
+
+impl HintCost {
+    pub const FREE: Self = Self::new(0, 0);
+
+    pub const fn new(mistakes: u8, points: u8) -> Self {
+        Self { mistakes, points }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HintCost {
+    pub fn free() -> Self {
+        Self::FREE
+    }
+
+    pub fn mistakes(mistakes: u8) -> Self {
+        Self::new(mistakes, 0)
+    }
+
+    pub fn points(points: u8) -> Self {
+        Self::new(0, points)
+    }
+
+    pub fn mistake_cost(&self) -> u8 {
+        self.mistakes
+    }
+
+    pub fn point_cost(&self) -> u8 {
+        self.points
+    }
+}
+
+/// The three ways of getting help, from least to most given away
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HintKind {
+    /// The next of a group's authored hints, and then its theme
+    Nudge,
+    Word,
+    Group,
+}
+
+/// A hint that's been taken, which is all a game needs to remember to give the next one
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UsedHint {
+    pub color: Color,
+    pub kind: HintKind,
+}
+
+impl UsedHint {
+    /// How the hint shows up in a shared result, without saying what it was
+    pub fn emoji(&self) -> String {
+        let kind = match self.kind {
+            HintKind::Nudge => "💡",
+            HintKind::Word => "🔤",
+            HintKind::Group => "🔓",
+        };
+        format!("{}{kind}", self.color.emoji())
+    }
+}
+
+/// What a nudge or a revealed word says about a group
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    color: Color,
+    kind: HintKind,
+    text: String,
+}
+
+impl Hint {
+    pub(super) fn new(color: Color, kind: HintKind, text: &str) -> Self {
+        Self {
+            color,
+            kind,
+            text: text.into(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Hint {
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn kind(&self) -> HintKind {
+        self.kind
+    }
+
+    /// The nudge itself, or the revealed word
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum HintError {
+    #[error("the game is over")]
+    GameOver,
+    #[error("the puzzle doesn't have that group")]
+    NoSuchGroup,
+    #[error("that group is already solved")]
+    AlreadySolved,
+    #[error("there's nothing more to give away about that group without solving it")]
+    NoneLeft,
+    #[error("the hint would cost the last mistake")]
+    TooCostly,
+    #[error("a sealed puzzle can't give anything away about a group before it's guessed")]
+    Sealed,
+}
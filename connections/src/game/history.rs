@@ -0,0 +1,55 @@
+/// Undo and redo stacks of whole snapshots, which are small enough for a board that copying
+/// them is simpler than recording how to reverse every action
+#[derive(Debug)]
+pub struct History<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> History<T> {
+    pub const fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Remembers `state` as the one to go back to. Anything that could be redone is forgotten,
+    /// since it branched off from an older state.
+    pub fn record(&mut self, state: T) {
+        self.undo.push(state);
+        self.redo.clear();
+    }
+
+    /// Swaps `current` for the last recorded state, keeping `current` around to be redone
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
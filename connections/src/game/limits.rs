@@ -0,0 +1,157 @@
+use super::alternative::Alternative;
+use super::color::MAX_GROUPS;
+use super::metadata::Metadata;
+use super::puzzle::{ConnectionSet, TranscodingError, MAX_GROUP_SIZE};
+use super::seal::SealedLayout;
+use std::io::Read;
+
+/// Far longer than the code of any reasonable puzzle, though text that doesn't compress can
+/// still fit the other limits and not this
+pub const MAX_CODE_LEN: usize = 32 * 1024;
+/// How big a puzzle can unpack to. Decompression stops here, so a code that would inflate
+/// to gigabytes costs no more than one that inflates to this.
+pub const MAX_DECOMPRESSED_LEN: usize = 64 * 1024;
+/// For words, themes, hints, labels, colors, titles, authors and tags
+pub const MAX_TEXT_LEN: usize = 256;
+/// For the intro and end note, which can run to a paragraph
+pub const MAX_NOTE_LEN: usize = 2048;
+/// Per group
+pub const MAX_HINTS: usize = 8;
+pub const MAX_ALTERNATIVES: usize = 16;
+pub const MAX_TAGS: usize = 16;
+/// Puzzles in a pack, which between them still have to fit [`MAX_DECOMPRESSED_LEN`]
+pub const MAX_PACK_LEN: usize = 64;
+
+/// Codes come from links anyone can make, so they're turned away before anything is done
+/// with them if they're longer than a puzzle could be
+pub fn code(code: &str) -> Result<(), TranscodingError> {
+    if code.len() > MAX_CODE_LEN {
+        return Err(TranscodingError::CodeTooLong {
+            limit: MAX_CODE_LEN,
+        });
+    }
+    Ok(())
+}
+
+/// Reads everything out of `decoder`, giving up once it's more than
+/// [`MAX_DECOMPRESSED_LEN`] bytes past the first `skip`
+pub fn decompressed(decoder: impl Read, skip: usize) -> Result<Vec<u8>, TranscodingError> {
+    let limit = skip + MAX_DECOMPRESSED_LEN;
+    let mut bytes = Vec::new();
+    decoder
+        .take(limit as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|_| TranscodingError::Decompress)?;
+    if bytes.len() > limit {
+        return Err(TranscodingError::TooLarge {
+            limit: MAX_DECOMPRESSED_LEN,
+        });
+    }
+    Ok(bytes)
+}
+
+/// Hands `code` back if it'll decode, which it won't if it's too long or if the puzzle's
+/// `postcard_bytes` unpack to more than [`MAX_DECOMPRESSED_LEN`]. Encoders check this on top
+/// of everything in the puzzle being within the limits, so no one makes a link that won't open.
+pub fn encoded(postcard_bytes: &[u8], code: String) -> Result<String, TranscodingError> {
+    if postcard_bytes.len() > MAX_DECOMPRESSED_LEN {
+        return Err(TranscodingError::TooLarge {
+            limit: MAX_DECOMPRESSED_LEN,
+        });
+    }
+    self::code(&code)?;
+    Ok(code)
+}
+
+/// Postcard can only make as much as there are bytes to make it from, so this runs once a
+/// puzzle's been read, before its shape is checked or anything's built from it
+pub fn puzzle(
+    sets: &[ConnectionSet],
+    alternatives: &[Alternative],
+    metadata: &Metadata,
+) -> Result<(), TranscodingError> {
+    count("groups", sets.len(), MAX_GROUPS)?;
+    for set in sets {
+        count("words in a group", set.len(), MAX_GROUP_SIZE)?;
+        for word in set.words_list_ref() {
+            text("word", word, MAX_TEXT_LEN)?;
+        }
+        group(set)?;
+    }
+    count(
+        "alternative solutions",
+        alternatives.len(),
+        MAX_ALTERNATIVES,
+    )?;
+    for alternative in alternatives {
+        // no card moves twice
+        let cards = MAX_GROUPS * MAX_GROUP_SIZE;
+        count("cards moved", alternative.moves().len(), cards)?;
+    }
+    self::metadata(metadata)
+}
+
+pub fn sealed(layout: &SealedLayout) -> Result<(), TranscodingError> {
+    count("groups", layout.groups.len(), MAX_GROUPS)?;
+    count("words in a group", layout.group_size, MAX_GROUP_SIZE)?;
+    count("words", layout.words.len(), MAX_GROUPS * MAX_GROUP_SIZE)?;
+    for word in &layout.words {
+        text("word", word, MAX_TEXT_LEN)?;
+    }
+    metadata(&layout.metadata)
+}
+
+pub fn pack(title: &str, len: usize) -> Result<(), TranscodingError> {
+    text("title", title, MAX_TEXT_LEN)?;
+    count("puzzles", len, MAX_PACK_LEN)
+}
+
+/// Everything about a group besides its words, which is all a sealed group's secret has
+pub fn group(set: &ConnectionSet) -> Result<(), TranscodingError> {
+    text("theme", set.theme_ref(), MAX_TEXT_LEN)?;
+    count("hints", set.hints().len(), MAX_HINTS)?;
+    for hint in set.hints() {
+        text("hint", hint, MAX_TEXT_LEN)?;
+    }
+    count("red herrings", set.herrings().len(), MAX_GROUP_SIZE)?;
+    text(
+        "label",
+        set.custom_label().unwrap_or_default(),
+        MAX_TEXT_LEN,
+    )?;
+    text("color", set.custom_hex().unwrap_or_default(), MAX_TEXT_LEN)
+}
+
+fn metadata(metadata: &Metadata) -> Result<(), TranscodingError> {
+    text("title", &metadata.title().unwrap_or_default(), MAX_TEXT_LEN)?;
+    text(
+        "author",
+        &metadata.author().unwrap_or_default(),
+        MAX_TEXT_LEN,
+    )?;
+    text("intro", &metadata.intro().unwrap_or_default(), MAX_NOTE_LEN)?;
+    text(
+        "end note",
+        &metadata.end_note().unwrap_or_default(),
+        MAX_NOTE_LEN,
+    )?;
+    count("tags", metadata.tags_ref().len(), MAX_TAGS)?;
+    for tag in metadata.tags_ref() {
+        text("tag", tag, MAX_TEXT_LEN)?;
+    }
+    Ok(())
+}
+
+fn text(field: &'static str, text: &str, limit: usize) -> Result<(), TranscodingError> {
+    if text.len() > limit {
+        return Err(TranscodingError::TooLong { field, limit });
+    }
+    Ok(())
+}
+
+fn count(field: &'static str, count: usize, limit: usize) -> Result<(), TranscodingError> {
+    if count > limit {
+        return Err(TranscodingError::TooMany { field, limit });
+    }
+    Ok(())
+}
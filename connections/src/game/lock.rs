@@ -0,0 +1,59 @@
+use super::puzzle::TranscodingError;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Encrypts a compressed puzzle under a key stretched from `passphrase`, as
+/// `salt ++ nonce ++ ciphertext`. `header` is the code's magic and version, which are
+/// authenticated along with the puzzle so they can't be swapped out.
+pub fn lock(passphrase: &str, header: &[u8], compressed_bytes: &[u8]) -> Vec<u8> {
+    let salt: [u8; SALT_LEN] = rand::random();
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let cipher = cipher(passphrase, &salt);
+    let payload = Payload {
+        msg: compressed_bytes,
+        aad: header,
+    };
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), payload)
+        .expect("error encrypting");
+
+    let mut bytes = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    bytes.extend(salt);
+    bytes.extend(nonce);
+    bytes.extend(ciphertext);
+    bytes
+}
+
+/// Undoes [`lock`]. A wrong passphrase and a tampered code look the same from here, so
+/// both are [`TranscodingError::Passphrase`].
+pub fn unlock(
+    passphrase: &str,
+    header: &[u8],
+    locked_bytes: &[u8],
+) -> Result<Vec<u8>, TranscodingError> {
+    if locked_bytes.len() < SALT_LEN + NONCE_LEN {
+        return Err(TranscodingError::Passphrase);
+    }
+    let (salt, rest) = locked_bytes.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let payload = Payload {
+        msg: ciphertext,
+        aad: header,
+    };
+    cipher(passphrase, salt)
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| TranscodingError::Passphrase)
+}
+
+/// Argon2id with its default cost, which takes a noticeable moment on purpose
+fn cipher(passphrase: &str, salt: &[u8]) -> ChaCha20Poly1305 {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .expect("the salt and key are fixed sizes argon2 accepts");
+    ChaCha20Poly1305::new(&key)
+}
@@ -0,0 +1,282 @@
+use super::board::{Board, Selection};
+use super::color::Color;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Every submission in a game, including the ones that didn't count, for working out how
+/// players go about solving a puzzle. Unlike the rest of the game it isn't undone or saved.
+#[derive(Debug, Clone, Default)]
+pub struct GuessLog {
+    entries: Vec<LoggedGuess>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LoggedGuess {
+    pub selection: Selection,
+    pub outcome: GuessOutcome,
+    pub at: DateTime<Utc>,
+    /// `None` when the rules have no mistake limit
+    pub mistakes_left: Option<u8>,
+}
+
+/// What the board made of a submission, whether or not the rules told the player. A one away
+/// guess is logged as one even when one-away feedback is off, and the guess that loses the
+/// game is still logged as the mismatch it was.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GuessOutcome {
+    Matched(Color),
+    OneAway,
+    Mismatch,
+    NotEnough,
+    AlreadyTried,
+    GameOver,
+}
+
+/// A logged guess with its cards written out as words, which is what gets exported
+#[derive(Serialize)]
+struct ExportedGuess<'a> {
+    at: DateTime<Utc>,
+    outcome: &'static str,
+    matched: Option<&'a str>,
+    mistakes_left: Option<u8>,
+    words: Vec<&'a str>,
+    /// `None` for the cards of sealed groups no one's found
+    groups: Vec<Option<&'a str>>,
+}
+
+impl GuessOutcome {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Matched(_) => "matched",
+            Self::OneAway => "one_away",
+            Self::Mismatch => "mismatch",
+            Self::NotEnough => "not_enough",
+            Self::AlreadyTried => "already_tried",
+            Self::GameOver => "game_over",
+        }
+    }
+}
+
+impl GuessLog {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub(super) fn record(
+        &mut self,
+        selection: Selection,
+        outcome: GuessOutcome,
+        mistakes_left: Option<u8>,
+    ) {
+        self.entries.push(LoggedGuess {
+            selection,
+            outcome,
+            at: Utc::now(),
+            mistakes_left,
+        });
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn entries(&self) -> &[LoggedGuess] {
+        &self.entries
+    }
+
+    /// An array with one object per guess, naming the selected words and their groups as
+    /// `board` has them now
+    pub fn to_json(&self, board: &Board) -> String {
+        serde_json::to_string(&self.export(board)).expect("error serializing")
+    }
+
+    /// One row per guess under a header, with the selected words and their groups separated
+    /// by `;` inside their columns
+    pub fn to_csv(&self, board: &Board) -> String {
+        let mut csv = String::from("at,outcome,matched,mistakes_left,words,groups\n");
+        for guess in self.export(board) {
+            let row = [
+                guess.at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                guess.outcome.to_string(),
+                guess.matched.unwrap_or_default().to_string(),
+                guess
+                    .mistakes_left
+                    .map_or(String::new(), |left| left.to_string()),
+                guess.words.join(";"),
+                guess
+                    .groups
+                    .iter()
+                    .map(|group| group.unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    fn export<'a>(&self, board: &'a Board) -> Vec<ExportedGuess<'a>> {
+        let puzzle = board.puzzle();
+        self.entries
+            .iter()
+            .map(|guess| ExportedGuess {
+                at: guess.at,
+                outcome: guess.outcome.name(),
+                matched: match guess.outcome {
+                    GuessOutcome::Matched(color) => Some(puzzle.label(color)),
+                    _ => None,
+                },
+                mistakes_left: guess.mistakes_left,
+                words: guess
+                    .selection
+                    .iter()
+                    .map(|card| card.word(puzzle.by_color(card.color())))
+                    .collect(),
+                groups: guess
+                    .selection
+                    .iter()
+                    .map(|card| {
+                        let group = board.group_of(card);
+                        puzzle.is_found(group).then(|| puzzle.label(group))
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Quotes a field if it has anything in it that would break up the row
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_field, GuessOutcome};
+    use crate::game::alternative::{Alternative, CardMove};
+    use crate::game::color::Color;
+    use crate::game::tests::guess;
+    use crate::game::{ConnectionPuzzle, GameState};
+    use serde_json::Value;
+
+    #[test]
+    fn logs_every_submission() {
+        let mut game = GameState::new(ConnectionPuzzle::default(), None);
+        let _ = guess(&mut game, &["wheat", "milk"]);
+        let _ = guess(&mut game, &["wheat", "milk", "eggs", "neon"]);
+        let _ = guess(&mut game, &["wheat", "milk", "eggs", "neon"]);
+        let _ = guess(&mut game, &["wheat", "milk", "eggs", "helium"]);
+        let _ = guess(&mut game, &["wheat", "milk", "eggs", "sugar"]);
+        let outcomes: Vec<GuessOutcome> = game.log().entries().iter().map(|e| e.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                GuessOutcome::NotEnough,
+                GuessOutcome::OneAway,
+                GuessOutcome::AlreadyTried,
+                GuessOutcome::OneAway,
+                GuessOutcome::Matched(Color::Yellow),
+            ]
+        );
+        let left: Vec<Option<u8>> = game
+            .log()
+            .entries()
+            .iter()
+            .map(|e| e.mistakes_left)
+            .collect();
+        assert_eq!(left, [Some(4), Some(3), Some(3), Some(2), Some(2)]);
+        let entries = game.log().entries();
+        assert!(entries.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    }
+
+    #[test]
+    fn exports_json_and_csv() {
+        let mut game = GameState::new(ConnectionPuzzle::default(), None);
+        let _ = guess(&mut game, &["wheat", "milk", "eggs", "sugar"]);
+        let _ = guess(&mut game, &["helium", "argon", "krypton", "war"]);
+        let log: Value = serde_json::from_str(&game.log_json()).unwrap();
+        assert_eq!(log[0]["outcome"], "matched");
+        assert_eq!(log[0]["matched"], "yellow");
+        assert_eq!(log[1]["outcome"], "one_away");
+        assert_eq!(log[1]["mistakes_left"], 3);
+        let mut words: Vec<&str> = log[1]["words"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|word| word.as_str().unwrap())
+            .collect();
+        words.sort_unstable();
+        assert_eq!(words, ["argon", "helium", "krypton", "war"]);
+
+        let csv = game.log_csv();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("at,outcome,matched,mistakes_left,words,groups")
+        );
+        let row: Vec<&str> = lines.nth(1).unwrap().split(',').collect();
+        assert_eq!(row[1..4], ["one_away", "", "3"]);
+        assert_eq!(row[4].split(';').count(), 4);
+    }
+
+    #[test]
+    fn resumed_games_start_a_new_log() {
+        let mut game = GameState::new(ConnectionPuzzle::default(), None);
+        let _ = guess(&mut game, &["wheat", "milk", "eggs", "sugar"]);
+        let code = game.save_code();
+        let resumed = GameState::resume_code(ConnectionPuzzle::default(), &code).unwrap();
+        assert_eq!(game.log().entries().len(), 1);
+        assert!(resumed.log().entries().is_empty());
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn sealed_groups_are_blank_until_found() {
+        let code = ConnectionPuzzle::default().encode_sealed();
+        let mut game = GameState::new(ConnectionPuzzle::decode(&code).unwrap(), None);
+        let _ = guess(&mut game, &["wheat", "milk", "eggs", "sugar"]);
+        let _ = guess(&mut game, &["helium", "argon", "krypton", "war"]);
+        let log: Value = serde_json::from_str(&game.log_json()).unwrap();
+
+        let found = log[0]["groups"].as_array().unwrap();
+        assert_eq!(found.len(), 4);
+        assert!(found.iter().all(|group| *group == log[0]["matched"]));
+        assert!(log[0]["matched"].is_string());
+        let unfound = log[1]["groups"].as_array().unwrap();
+        assert!(unfound.iter().all(Value::is_null));
+
+        let csv = game.log_csv();
+        let row = csv.lines().nth(2).unwrap();
+        assert!(row.ends_with(",;;;"));
+    }
+
+    #[test]
+    fn groups_follow_the_solution_played() {
+        let swap = Alternative::new(vec![
+            CardMove::new(Color::Yellow, 0, Color::Blue),
+            CardMove::new(Color::Blue, 0, Color::Yellow),
+        ]);
+        let puzzle = ConnectionPuzzle::default().with_alternative(swap).unwrap();
+        let mut game = GameState::new(puzzle, None);
+        let _ = guess(&mut game, &["helium", "milk", "eggs", "sugar"]);
+        let log: Value = serde_json::from_str(&game.log_json()).unwrap();
+
+        assert_eq!(log[0]["outcome"], "matched");
+        let groups = log[0]["groups"].as_array().unwrap();
+        assert!(groups.iter().all(|group| *group == log[0]["matched"]));
+    }
+}
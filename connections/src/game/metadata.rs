@@ -0,0 +1,115 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Everything about a puzzle besides the puzzle itself. All of it is optional, and a puzzle
+/// without any encodes the same as it did before metadata existed.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Metadata {
+    title: Option<String>,
+    author: Option<String>,
+    created: Option<NaiveDate>,
+    /// Shown before the game starts
+    intro: Option<String>,
+    /// Shown once the game is over
+    end_note: Option<String>,
+    tags: Vec<String>,
+}
+
+impl Metadata {
+    pub const fn new() -> Self {
+        Self {
+            title: None,
+            author: None,
+            created: None,
+            intro: None,
+            end_note: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_title(self, title: &str) -> Self {
+        Self {
+            title: Some(title.into()),
+            ..self
+        }
+    }
+
+    pub fn with_author(self, author: &str) -> Self {
+        Self {
+            author: Some(author.into()),
+            ..self
+        }
+    }
+
+    pub fn with_created(self, created: NaiveDate) -> Self {
+        Self {
+            created: Some(created),
+            ..self
+        }
+    }
+
+    pub fn with_intro(self, intro: &str) -> Self {
+        Self {
+            intro: Some(intro.into()),
+            ..self
+        }
+    }
+
+    pub fn with_end_note(self, end_note: &str) -> Self {
+        Self {
+            end_note: Some(end_note.into()),
+            ..self
+        }
+    }
+
+    pub fn with_tags(self, tags: &[&str]) -> Self {
+        Self {
+            tags: tags.iter().map(|&tag| tag.into()).collect(),
+            ..self
+        }
+    }
+
+    pub fn created_on(&self) -> Option<NaiveDate> {
+        self.created
+    }
+
+    pub fn tags_ref(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::new()
+    }
+}
+
+/// Dates cross into JS as `YYYY-MM-DD` strings
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Metadata {
+    pub fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
+
+    pub fn author(&self) -> Option<String> {
+        self.author.clone()
+    }
+
+    pub fn created(&self) -> Option<String> {
+        self.created.map(|date| date.to_string())
+    }
+
+    pub fn intro(&self) -> Option<String> {
+        self.intro.clone()
+    }
+
+    pub fn end_note(&self) -> Option<String> {
+        self.end_note.clone()
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.tags.clone()
+    }
+}
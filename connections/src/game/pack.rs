@@ -0,0 +1,278 @@
+use super::envelope::{self, CodeVersion, PACK_VERSION};
+use super::limits;
+use super::puzzle::{ConnectionPuzzle, TranscodingError};
+use super::rules::Rules;
+use super::signing::{self, Keyring};
+use super::GameState;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Puzzles to play in order, like a week of them for a class or a club, under one code.
+/// They're compressed together, so a word or theme that comes up in more than one puzzle
+/// costs a back reference after the first time. Puzzles are numbered from 1.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "PackPuzzles")]
+pub struct PuzzlePack {
+    title: String,
+    puzzles: Vec<ConnectionPuzzle>,
+}
+
+/// What a pack looks like as JSON before it's been checked against the [`limits`]
+#[derive(Deserialize)]
+struct PackPuzzles {
+    title: String,
+    puzzles: Vec<ConnectionPuzzle>,
+}
+
+impl TryFrom<PackPuzzles> for PuzzlePack {
+    type Error = TranscodingError;
+
+    fn try_from(PackPuzzles { title, puzzles }: PackPuzzles) -> Result<Self, TranscodingError> {
+        limits::pack(&title, puzzles.len())?;
+        if puzzles.is_empty() {
+            return Err(TranscodingError::EmptyPack);
+        }
+        puzzles
+            .into_iter()
+            .try_fold(Self::new(&title), Self::with_puzzle)
+    }
+}
+
+/// How a pack is laid out in a code: the title, then each puzzle as its layout version and
+/// the bytes it would have in a code of its own before compression
+type PackBody = (String, Vec<(u8, Vec<u8>)>);
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PuzzlePack {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.into(),
+            puzzles: Vec::new(),
+        }
+    }
+
+    /// Adds `puzzle` after the rest. A puzzle from a locked code can't be added, since the
+    /// pack's code would give its answers away.
+    pub fn with_puzzle(mut self, puzzle: ConnectionPuzzle) -> Result<Self, TranscodingError> {
+        if puzzle.came_locked() {
+            return Err(TranscodingError::Locked);
+        }
+        self.puzzles.push(puzzle);
+        Ok(self)
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.puzzles.is_empty()
+    }
+
+    /// Starts a game of puzzle `number`, by the puzzle's own rules unless `rules` overrides
+    /// them
+    pub fn start(&self, number: u32, rules: Option<Rules>) -> Result<GameState, PackError> {
+        Ok(GameState::new(self.number(number)?.clone(), rules))
+    }
+
+    pub fn encode(&self) -> String {
+        ConnectionPuzzle::code(PACK_VERSION, &self.serialize())
+    }
+
+    /// [`Self::encode`], unless the code would be over the [`limits`] and so wouldn't decode
+    pub fn try_encode(&self) -> Result<String, TranscodingError> {
+        limits::pack(&self.title, self.len())?;
+        for puzzle in &self.puzzles {
+            puzzle.within_limits()?;
+        }
+        let postcard_bytes = self.serialize();
+        limits::encoded(
+            &postcard_bytes,
+            ConnectionPuzzle::code(PACK_VERSION, &postcard_bytes),
+        )
+    }
+
+    /// A single puzzle's code decodes too, as a pack of just that puzzle under its title
+    pub fn decode(code: &str) -> Result<Self, TranscodingError> {
+        if !Self::is_pack(code) {
+            let puzzle = ConnectionPuzzle::decode(code)?;
+            let title = puzzle.metadata().title().unwrap_or_default();
+            return Self::new(&title).with_puzzle(puzzle);
+        }
+        let bytes = ConnectionPuzzle::unwrap_base64(signing::unsigned(code))?;
+        let (version, compressed_bytes) = envelope::open(&bytes)?;
+        let postcard_bytes = ConnectionPuzzle::decompress(version, compressed_bytes)?;
+        Self::deserialize(&postcard_bytes)
+    }
+
+    /// Decodes `code` and checks its signature against `keyring`, which vouches for every
+    /// puzzle in it
+    pub fn decode_trusted(code: &str, keyring: &Keyring) -> Result<Self, TranscodingError> {
+        let pack = Self::decode(code)?;
+        let puzzles = pack
+            .puzzles
+            .into_iter()
+            .map(|puzzle| puzzle.verified(code, keyring))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { puzzles, ..pack })
+    }
+
+    /// Whether `code` is for a pack, not a single puzzle
+    pub fn is_pack(code: &str) -> bool {
+        ConnectionPuzzle::unwrap_base64(signing::unsigned(code))
+            .is_ok_and(|bytes| matches!(envelope::open(&bytes), Ok((CodeVersion::V5, _))))
+    }
+}
+
+impl PuzzlePack {
+    pub fn puzzles(&self) -> &[ConnectionPuzzle] {
+        &self.puzzles
+    }
+
+    pub fn number(&self, number: u32) -> Result<&ConnectionPuzzle, PackError> {
+        (number as usize)
+            .checked_sub(1)
+            .and_then(|index| self.puzzles.get(index))
+            .ok_or(PackError::NoNumber(number))
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let puzzles: Vec<(u8, Vec<u8>)> = self
+            .puzzles
+            .iter()
+            .map(ConnectionPuzzle::serialize_version)
+            .collect();
+        postcard::to_allocvec(&(&self.title, puzzles)).expect("error serializing")
+    }
+
+    /// Each puzzle is read the way a code of its own version would be, limits and all
+    pub fn deserialize(postcard_bytes: &[u8]) -> Result<Self, TranscodingError> {
+        let (title, puzzles): PackBody =
+            postcard::from_bytes(postcard_bytes).map_err(|_| TranscodingError::Postcard)?;
+        limits::pack(&title, puzzles.len())?;
+        if puzzles.is_empty() {
+            return Err(TranscodingError::EmptyPack);
+        }
+        let puzzles = puzzles
+            .iter()
+            .map(
+                |(version, postcard_bytes)| match CodeVersion::from_number(*version)? {
+                    // packs don't go inside packs
+                    CodeVersion::V5 => Err(TranscodingError::Postcard),
+                    version => ConnectionPuzzle::deserialize_version(version, postcard_bytes),
+                },
+            )
+            .collect::<Result<_, _>>()?;
+        Ok(Self { title, puzzles })
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl PuzzlePack {
+    pub fn from_json(json: &str) -> Result<PuzzlePack, PackError> {
+        serde_json::from_str(json).map_err(|_| PackError::Format)
+    }
+
+    pub fn puzzle_number(&self, number: u32) -> Result<ConnectionPuzzle, PackError> {
+        self.number(number).cloned()
+    }
+}
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum PackError {
+    #[error("there's no puzzle #{0} in the pack")]
+    NoNumber(u32),
+    #[error("couldn't read the pack")]
+    Format,
+}
+
+#[cfg(feature = "wasm")]
+impl From<PackError> for JsValue {
+    fn from(error: PackError) -> Self {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack() -> PuzzlePack {
+        PuzzlePack::new("Week one")
+            .with_puzzle(ConnectionPuzzle::default())
+            .and_then(|pack| pack.with_puzzle(ConnectionPuzzle::decode("debug")?))
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        let code = pack().encode();
+        assert!(PuzzlePack::is_pack(&code));
+        let decoded = PuzzlePack::decode(&code).unwrap();
+        assert_eq!(decoded.title(), "Week one");
+        assert_eq!(decoded.serialize(), pack().serialize());
+        assert!(decoded.start(2, None).is_ok());
+        assert_eq!(decoded.number(3).unwrap_err(), PackError::NoNumber(3));
+        assert_eq!(decoded.number(0).unwrap_err(), PackError::NoNumber(0));
+    }
+
+    #[test]
+    fn keeps_sealed_puzzles_sealed() {
+        let sealed = ConnectionPuzzle::decode(&ConnectionPuzzle::default().encode_sealed());
+        let pack = PuzzlePack::new("Sealed")
+            .with_puzzle(sealed.unwrap())
+            .unwrap();
+        let decoded = PuzzlePack::decode(&pack.encode()).unwrap();
+        assert!(decoded.number(1).unwrap().is_sealed());
+    }
+
+    #[test]
+    fn single_puzzle_is_a_pack_of_one() {
+        let code = ConnectionPuzzle::default().encode();
+        assert!(!PuzzlePack::is_pack(&code));
+        assert_eq!(
+            ConnectionPuzzle::decode(&pack().encode()).unwrap_err(),
+            TranscodingError::Pack
+        );
+        assert_eq!(PuzzlePack::decode(&code).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn locked_puzzles_stay_out() {
+        let code = ConnectionPuzzle::default()
+            .encode_locked("hunter2")
+            .unwrap();
+        let locked = ConnectionPuzzle::decode_with(&code, Some("hunter2")).unwrap();
+        assert_eq!(
+            PuzzlePack::new("Locked").with_puzzle(locked).unwrap_err(),
+            TranscodingError::Locked
+        );
+        assert_eq!(
+            PuzzlePack::decode(&code).unwrap_err(),
+            TranscodingError::Passphrase
+        );
+    }
+
+    #[test]
+    fn json_packs_are_checked_like_codes() {
+        let empty = r#"{"title": "Empty", "puzzles": []}"#;
+        assert!(serde_json::from_str::<PuzzlePack>(empty).is_err());
+        let long_title = serde_json::json!({
+            "title": "a".repeat(limits::MAX_TEXT_LEN + 1),
+            "puzzles": [ConnectionPuzzle::default()],
+        });
+        assert!(serde_json::from_value::<PuzzlePack>(long_title).is_err());
+        let json = serde_json::to_string(&pack()).unwrap();
+        let read: PuzzlePack = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.serialize(), pack().serialize());
+    }
+}
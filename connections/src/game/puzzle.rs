@@ -1,151 +1,296 @@
-use super::color::Color;
+use super::alternative::Alternative;
+use super::checksum;
+use super::color::{Color, ColorIter, MAX_GROUPS};
+use super::dictionary;
+use super::envelope::{
+    self, CodeVersion, CURRENT_VERSION, LATEST_VERSION, LOCKED_VERSION, SEALED_VERSION,
+};
+use super::herring::RedHerring;
+use super::limits;
+use super::lock;
+use super::metadata::Metadata;
+use super::rules::Rules;
+use super::seal::{Seal, SealedGroup, SealedLayout, SALT_LEN};
+use super::signing::{self, Keyring};
+use super::validate::{validate, ValidationError};
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
-use flate2::write::GzDecoder;
+use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use std::io::Write;
-use std::ops::Deref;
 use thiserror::Error;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-#[allow(unused_imports)]
-use web_sys::console;
-
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "PuzzleSets")]
 pub struct ConnectionPuzzle {
-    yellow: YellowSet,
-    blue: BlueSet,
-    purple: PurpleSet,
-    green: GreenSet,
+    sets: Vec<ConnectionSet>,
+    rules: Rules,
+    alternatives: Vec<Alternative>,
+    metadata: Metadata,
+    /// Only for puzzles decoded from sealed codes, whose groups are found as they're guessed
+    #[serde(skip)]
+    seal: Option<Seal>,
+    /// Who signed the code the puzzle came from, when it's someone trusted
+    #[serde(skip)]
+    signer: Option<String>,
+    /// The code a puzzle decoded with a passphrase came from, since any code made from the
+    /// puzzle itself would give its answers away
+    #[serde(skip)]
+    locked_code: Option<String>,
 }
 
-#[wasm_bindgen]
-#[repr(transparent)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct BlueSet(ConnectionSet);
-
-#[wasm_bindgen]
-#[repr(transparent)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct YellowSet(ConnectionSet);
-
-#[wasm_bindgen]
-#[repr(transparent)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct PurpleSet(ConnectionSet);
+pub const MIN_GROUPS: usize = 2;
+pub const MIN_GROUP_SIZE: usize = 2;
+pub const MAX_GROUP_SIZE: usize = 8;
 
-#[wasm_bindgen]
-#[repr(transparent)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct GreenSet(ConnectionSet);
+/// What a puzzle looks like on the wire before its shape has been checked
+#[derive(Deserialize)]
+struct PuzzleSets {
+    sets: Vec<ConnectionSet>,
+    #[serde(default)]
+    rules: Rules,
+    #[serde(default)]
+    alternatives: Vec<Alternative>,
+    #[serde(default)]
+    metadata: Metadata,
+}
 
-impl Deref for BlueSet {
-    type Target = ConnectionSet;
+impl TryFrom<PuzzleSets> for ConnectionPuzzle {
+    type Error = ShapeError;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    fn try_from(
+        PuzzleSets {
+            sets,
+            rules,
+            alternatives,
+            metadata,
+        }: PuzzleSets,
+    ) -> Result<Self, ShapeError> {
+        // puzzles read from JSON files are checked the same way as ones read from codes
+        limits::puzzle(&sets, &alternatives, &metadata).map_err(ShapeError::Limit)?;
+        let puzzle = Self::try_new(sets)?
+            .with_rules(rules)
+            .with_metadata(metadata);
+        let mut puzzle = alternatives
+            .into_iter()
+            .try_fold(puzzle, Self::with_alternative)?;
+        if !puzzle.herrings_fit() {
+            return Err(ShapeError::Herring);
+        }
+        puzzle.tidy_labels();
+        Ok(puzzle)
     }
 }
-impl Deref for PurpleSet {
-    type Target = ConnectionSet;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-impl Deref for GreenSet {
-    type Target = ConnectionSet;
+/// How a puzzle is laid out in a code. Metadata goes after it, and only when there is some,
+/// so codes from before metadata still decode and puzzles without it keep the same code.
+type PuzzleBody = (Vec<ConnectionSet>, Rules, Vec<Alternative>);
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+#[derive(Debug, Error, PartialEq, Eq, Clone, Copy)]
+pub enum ShapeError {
+    #[error("a puzzle needs between {MIN_GROUPS} and {MAX_GROUPS} groups, not {0}")]
+    GroupCount(usize),
+    #[error("a group needs between {MIN_GROUP_SIZE} and {MAX_GROUP_SIZE} words, not {0}")]
+    GroupSize(usize),
+    #[error("every group needs the same number of words")]
+    Uneven,
+    #[error("an alternative solution has to move cards of the puzzle and keep the groups even")]
+    Alternative,
+    #[error("a red herring has to be a word of its group and point at a group of the puzzle")]
+    Herring,
+    #[error("{0}")]
+    Limit(TranscodingError),
 }
-impl Deref for YellowSet {
-    type Target = ConnectionSet;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+/// The layout every code used back when puzzles were always four groups of four.
+/// Codes shared from then still decode through it.
+#[derive(Deserialize)]
+struct LegacyPuzzle {
+    yellow: LegacySet,
+    blue: LegacySet,
+    purple: LegacySet,
+    green: LegacySet,
 }
 
-#[wasm_bindgen]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-pub struct ConnectionSet {
+#[derive(Deserialize)]
+struct LegacySet {
     theme: String,
     words: [String; 4],
 }
 
-impl From<YellowSet> for ConnectionSet {
-    fn from(set: YellowSet) -> Self {
-        set.0
+impl From<LegacySet> for ConnectionSet {
+    fn from(LegacySet { theme, words }: LegacySet) -> Self {
+        Self {
+            theme,
+            words: words.into(),
+            hints: Vec::new(),
+            herrings: Vec::new(),
+            style: GroupStyle::default(),
+        }
     }
 }
 
-impl From<GreenSet> for ConnectionSet {
-    fn from(set: GreenSet) -> Self {
-        set.0
+impl From<LegacyPuzzle> for ConnectionPuzzle {
+    fn from(legacy: LegacyPuzzle) -> Self {
+        // the legacy field order is also the color order
+        let sets = [legacy.yellow, legacy.blue, legacy.purple, legacy.green];
+        Self {
+            sets: sets.into_iter().map(Into::into).collect(),
+            rules: Rules::default(),
+            alternatives: Vec::new(),
+            metadata: Metadata::new(),
+            seal: None,
+            signer: None,
+            locked_code: None,
+        }
     }
 }
 
-impl From<PurpleSet> for ConnectionSet {
-    fn from(set: PurpleSet) -> Self {
-        set.0
-    }
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ConnectionSet {
+    theme: String,
+    words: Vec<String>,
+    /// Nudges toward the theme, vaguest first
+    #[serde(default)]
+    hints: Vec<String>,
+    #[serde(default)]
+    herrings: Vec<RedHerring>,
+    #[serde(default)]
+    style: GroupStyle,
 }
 
-impl From<BlueSet> for ConnectionSet {
-    fn from(set: BlueSet) -> Self {
-        set.0
-    }
+/// How a group looks and where it falls in difficulty. Anything left out comes from the
+/// group's color, so puzzles without a style get the NYT palette.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[serde(default)]
+struct GroupStyle {
+    /// The group's name in place of its color
+    label: Option<String>,
+    /// A `#rrggbb` color
+    hex: Option<String>,
+    /// Lower is easier
+    rank: Option<u8>,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl ConnectionSet {
     pub fn theme(&self) -> String {
         self.theme.clone()
     }
 
     pub fn words_list(&self) -> Box<[String]> {
-        Box::new(self.words.clone())
+        self.words.clone().into_boxed_slice()
+    }
+
+    pub fn hints_list(&self) -> Box<[String]> {
+        self.hints.clone().into_boxed_slice()
+    }
+
+    /// The group word `word_index` is meant to be mistaken for, if it's a red herring
+    pub fn herring_for(&self, word_index: usize) -> Option<Color> {
+        self.herrings
+            .iter()
+            .find(|herring| herring.word_index == word_index)
+            .map(|herring| herring.decoy)
     }
 }
 
 impl ConnectionSet {
-    fn new(theme: &str, words: [&str; 4]) -> Self {
-        let words: [String; 4] = [
-            words[0].into(),
-            words[1].into(),
-            words[2].into(),
-            words[3].into(),
-        ];
+    pub fn new(theme: &str, words: &[&str]) -> Self {
         Self {
             theme: theme.into(),
-            words,
+            words: words.iter().map(|&word| word.into()).collect(),
+            hints: Vec::new(),
+            herrings: Vec::new(),
+            style: GroupStyle::default(),
         }
     }
 
-    pub fn theme_ref(&self) -> &str {
-        &self.theme
+    /// Hints go from a vague nudge to almost giving the theme away. The theme itself is
+    /// always the last hint, so it doesn't need to be one of them.
+    pub fn with_hints(self, hints: &[&str]) -> Self {
+        Self {
+            hints: hints.iter().map(|&hint| hint.into()).collect(),
+            ..self
+        }
+    }
+
+    pub fn hints(&self) -> &[String] {
+        &self.hints
     }
 
-    pub fn words_list_ref(&self) -> [&str; 4] {
-        self.words.each_ref().map(|str| str.as_str())
+    /// Marks word `word_index` as planted to look like it belongs to the `decoy` group
+    pub fn with_herring(mut self, word_index: usize, decoy: Color) -> Self {
+        self.herrings.push(RedHerring { word_index, decoy });
+        self
     }
 
-    const fn empty_set() -> Self {
+    /// The parts of a group a sealed code keeps hidden until it's found
+    fn secret(&self) -> Self {
         Self {
-            theme: String::new(),
-            words: [const { String::new() }; 4],
+            theme: self.theme.clone(),
+            words: Vec::new(),
+            hints: Vec::new(),
+            herrings: Vec::new(),
+            style: self.style.clone(),
         }
     }
 
+    pub fn herrings(&self) -> &[RedHerring] {
+        &self.herrings
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.style.label = Some(label.into());
+        self
+    }
+
+    pub fn with_hex(mut self, hex: &str) -> Self {
+        self.style.hex = Some(hex.into());
+        self
+    }
+
+    pub fn with_rank(mut self, rank: u8) -> Self {
+        self.style.rank = Some(rank);
+        self
+    }
+
+    pub fn custom_label(&self) -> Option<&str> {
+        self.style.label.as_deref()
+    }
+
+    pub fn custom_hex(&self) -> Option<&str> {
+        self.style.hex.as_deref()
+    }
+
+    pub fn custom_rank(&self) -> Option<u8> {
+        self.style.rank
+    }
+
+    pub fn theme_ref(&self) -> &str {
+        &self.theme
+    }
+
+    pub fn words_list_ref(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
     pub fn words(&self) -> String {
-        format!(
-            "{}, {}, {}, {}",
-            self.words[0], self.words[1], self.words[2], self.words[3]
-        )
+        self.words.join(", ")
     }
 }
 
@@ -189,30 +334,45 @@ fn args_as_ref((theme, words): &(String, [String; 4])) -> (&str, [&str; 4]) {
 }
 */
 
-fn js_args(slice: &[String]) -> (&str, [&str; 4]) {
-    assert_eq!(slice.len(), 5);
-    (&slice[0], [&slice[1], &slice[2], &slice[3], &slice[4]])
+/// An empty slice is a group with no theme and no words, which validation reports
+fn js_args(slice: &[String]) -> ConnectionSet {
+    let (theme, words) = slice
+        .split_first()
+        .map_or(("", &[][..]), |(theme, words)| (theme.as_str(), words));
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    ConnectionSet::new(theme, &words)
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl ConnectionPuzzle {
     pub fn decode(code: &str) -> Result<Self, TranscodingError> {
-        if code == "debug" {
-            return Ok(Self::debug());
-        } else if code == "default" {
-            return Ok(Self::default());
-        }
-        let compressed_bytes = URL_SAFE
-            .decode(code)
-            .map_err(|_| TranscodingError::Base64)?;
+        Self::decode_with(code, None)
+    }
 
-        let mut decoder = GzDecoder::new(Vec::new());
-        decoder
-            .write_all(&compressed_bytes[..])
-            .map_err(|_| TranscodingError::Gzip)?;
+    /// Decodes `code` and checks its signature against `keyring`, see [`Self::signer`]
+    pub fn decode_trusted(code: &str, keyring: &Keyring) -> Result<Self, TranscodingError> {
+        Self::decode_trusted_with(code, None, keyring)
+    }
 
-        let postcard_bytes = decoder.finish().unwrap();
-        postcard::from_bytes(&postcard_bytes[..]).map_err(|_| TranscodingError::Postcard)
+    /// [`Self::decode_locked`] and [`Self::decode_trusted`] at once
+    pub fn decode_locked_trusted(
+        code: &str,
+        passphrase: &str,
+        keyring: &Keyring,
+    ) -> Result<Self, TranscodingError> {
+        Self::decode_trusted_with(code, Some(passphrase), keyring)
+    }
+
+    /// Whether a code needs a passphrase to decode
+    pub fn is_locked(code: &str) -> bool {
+        Self::unwrap_base64(signing::unsigned(code))
+            .is_ok_and(|bytes| matches!(envelope::open(&bytes), Ok((CodeVersion::V3, _))))
+    }
+
+    /// Decodes a code made by [`Self::encode_locked`]. Codes that aren't locked decode the
+    /// same as they do without a passphrase.
+    pub fn decode_locked(code: &str, passphrase: &str) -> Result<Self, TranscodingError> {
+        Self::decode_with(code, Some(passphrase))
     }
 
     pub fn from_js(
@@ -220,12 +380,15 @@ impl ConnectionPuzzle {
         blue: Box<[String]>,
         purple: Box<[String]>,
         green: Box<[String]>,
-    ) -> Self {
-        let yellow = js_args(&yellow);
-        let blue = js_args(&blue);
-        let purple = js_args(&purple);
-        let green = js_args(&green);
-        Self::new(yellow, blue, purple, green)
+    ) -> Result<Self, ValidationError> {
+        let sets = [yellow, blue, purple, green];
+        Self::validated(sets.iter().map(|set| js_args(set)).collect())
+    }
+
+    /// Builds a puzzle of any size from `[theme, word, word, ..., theme, word, ...]`, where
+    /// each group is a theme followed by `group_size` words
+    pub fn from_js_groups(group_size: usize, groups: Vec<String>) -> Result<Self, ValidationError> {
+        Self::validated(groups.chunks(group_size + 1).map(js_args).collect())
     }
 
     pub fn new_code(
@@ -233,91 +396,494 @@ impl ConnectionPuzzle {
         blue: Box<[String]>,
         purple: Box<[String]>,
         green: Box<[String]>,
-    ) -> String {
-        Self::from_js(yellow, blue, purple, green).encode()
+    ) -> Result<String, ValidationError> {
+        Ok(Self::from_js(yellow, blue, purple, green)?.encode())
     }
 
     pub fn encode(&self) -> String {
-        let postcard_bytes: Vec<u8> = postcard::to_allocvec(&self).expect("error serializing");
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
-        encoder.write_all(&postcard_bytes).unwrap();
-        let compressed_bytes = encoder.finish().unwrap();
-        URL_SAFE.encode(&compressed_bytes)
+        let (version, postcard_bytes) = self.serialize_version();
+        Self::code(version, &postcard_bytes)
+    }
+
+    /// [`Self::encode`], unless the code would be over the [`limits`] and so wouldn't decode
+    pub fn try_encode(&self) -> Result<String, TranscodingError> {
+        self.within_limits()?;
+        let (version, postcard_bytes) = self.serialize_version();
+        limits::encoded(&postcard_bytes, Self::code(version, &postcard_bytes))
+    }
+
+    /// A code that can only be decoded with `passphrase`, for quizzes where players shouldn't
+    /// be able to read the answers out of the link. Stretching the passphrase is deliberately
+    /// slow, so this and decoding it take a moment. A sealed puzzle only has its words to
+    /// lock, so it can't be.
+    pub fn encode_locked(&self, passphrase: &str) -> Result<String, TranscodingError> {
+        if self.is_sealed() {
+            return Err(TranscodingError::Sealed);
+        }
+        Ok(Self::lock_code(passphrase, &self.serialize()))
+    }
+
+    /// [`Self::encode_locked`], unless the code would be over the [`limits`]
+    pub fn try_encode_locked(&self, passphrase: &str) -> Result<String, TranscodingError> {
+        if self.is_sealed() {
+            return Err(TranscodingError::Sealed);
+        }
+        self.within_limits()?;
+        let postcard_bytes = self.serialize();
+        limits::encoded(
+            &postcard_bytes,
+            Self::lock_code(passphrase, &postcard_bytes),
+        )
+    }
+
+    /// A code that shows the words but keeps each group's words, theme and style hidden until
+    /// it's guessed. Hints, red herrings and alternative solutions would give groups away, so
+    /// they're left out.
+    pub fn encode_sealed(&self) -> String {
+        if self.is_sealed() {
+            return self.encode();
+        }
+        Self::code(SEALED_VERSION, &self.sealed_layout())
+    }
+
+    /// [`Self::encode_sealed`], unless the code would be over the [`limits`]
+    pub fn try_encode_sealed(&self) -> Result<String, TranscodingError> {
+        if self.is_sealed() {
+            return self.try_encode();
+        }
+        self.within_limits()?;
+        let postcard_bytes = self.sealed_layout();
+        limits::encoded(&postcard_bytes, Self::code(SEALED_VERSION, &postcard_bytes))
+    }
+
+    /// The code to pass the puzzle on with, which for a puzzle from a locked code is that
+    /// code, still locked
+    pub fn share_code(&self) -> String {
+        self.locked_code.clone().unwrap_or_else(|| self.encode())
+    }
+
+    /// Whether the puzzle came from a locked code, see [`Self::share_code`]
+    pub fn came_locked(&self) -> bool {
+        self.locked_code.is_some()
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.seal.is_some()
+    }
+
+    /// Whether anyone can know what's in the group yet, which is always unless the puzzle
+    /// is sealed and the group hasn't been guessed
+    pub fn is_found(&self, color: Color) -> bool {
+        self.seal.as_ref().is_none_or(|seal| seal.is_found(color))
+    }
+
+    /// `None` if the puzzle doesn't have the group
+    pub fn yellow_owned(&self) -> Option<ConnectionSet> {
+        self.set_owned(Color::Yellow.index())
+    }
+
+    pub fn blue_owned(&self) -> Option<ConnectionSet> {
+        self.set_owned(Color::Blue.index())
+    }
+    pub fn green_owned(&self) -> Option<ConnectionSet> {
+        self.set_owned(Color::Green.index())
+    }
+    pub fn purple_owned(&self) -> Option<ConnectionSet> {
+        self.set_owned(Color::Purple.index())
+    }
+
+    pub fn set_owned(&self, index: usize) -> Option<ConnectionSet> {
+        self.sets.get(index).cloned()
+    }
+
+    pub fn num_groups(&self) -> usize {
+        self.sets.len()
+    }
+
+    pub fn group_size(&self) -> usize {
+        self.sets.first().map_or(0, ConnectionSet::len)
     }
 
-    pub fn yellow_owned(&self) -> ConnectionSet {
-        self.yellow.clone().into()
+    pub fn rules(&self) -> Rules {
+        self.rules
     }
 
-    pub fn blue_owned(&self) -> ConnectionSet {
-        self.blue.clone().into()
+    /// Blank if the puzzle doesn't have the group
+    pub fn group_label(&self, color: Color) -> String {
+        if self.has_group(color) {
+            self.label(color).into()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Blank if the puzzle doesn't have the group
+    pub fn group_hex(&self, color: Color) -> String {
+        if self.has_group(color) {
+            self.hex(color).into()
+        } else {
+            String::new()
+        }
+    }
+
+    /// 0 if the puzzle doesn't have the group
+    pub fn group_rank(&self, color: Color) -> u8 {
+        if self.has_group(color) {
+            self.rank(color)
+        } else {
+            0
+        }
     }
-    pub fn green_owned(&self) -> ConnectionSet {
-        self.green.clone().into()
+
+    /// Finds a group by its label or color, see [`ConnectionPuzzle::group_named`]
+    pub fn find_group(&self, name: &str) -> Option<Color> {
+        self.group_named(name)
     }
-    pub fn purple_owned(&self) -> ConnectionSet {
-        self.purple.clone().into()
+
+    /// The rules a `GameState` will use for this puzzle unless it's given others
+    pub fn with_rules(self, rules: Rules) -> Self {
+        Self { rules, ..self }
+    }
+
+    pub fn with_metadata(self, metadata: Metadata) -> Self {
+        Self { metadata, ..self }
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = metadata)]
+    pub fn js_metadata(&self) -> Metadata {
+        self.metadata.clone()
     }
 }
+
+/// The individual stages of a puzzle code, in the order `encode` applies them, with the
+/// version put in front of the compressed bytes. `decode` runs them in reverse: base64, then
+/// the version, then decompression, then postcard.
 impl ConnectionPuzzle {
-    pub fn yellow(&self) -> &ConnectionSet {
-        &self.yellow
+    /// Decodes `code` and checks its signature against `keyring`. A passphrase is only needed
+    /// for locked codes.
+    pub fn decode_trusted_with(
+        code: &str,
+        passphrase: Option<&str>,
+        keyring: &Keyring,
+    ) -> Result<Self, TranscodingError> {
+        Self::decode_with(code, passphrase)?.verified(code, keyring)
     }
 
-    pub fn blue(&self) -> &ConnectionSet {
-        &self.blue
+    /// Notes who signed `code` if `keyring` trusts them. It has to be the code the puzzle was
+    /// just decoded from, which is why this is only reached through decoding.
+    pub(super) fn verified(
+        mut self,
+        code: &str,
+        keyring: &Keyring,
+    ) -> Result<Self, TranscodingError> {
+        self.signer = keyring.signer(code)?.map(Into::into);
+        Ok(self)
     }
-    pub fn green(&self) -> &ConnectionSet {
-        &self.green
+
+    /// Decodes `code` whether or not it's signed, without checking the signature
+    pub fn decode_with(code: &str, passphrase: Option<&str>) -> Result<Self, TranscodingError> {
+        let locked_code = code;
+        let code = signing::unsigned(code);
+        if code == "debug" {
+            return Ok(Self::debug());
+        } else if code == "default" {
+            return Ok(Self::default());
+        }
+        let bytes = Self::unwrap_base64(code)?;
+        let (version, body) = envelope::open(&bytes)?;
+        let unlocked_bytes;
+        let compressed_bytes = if version == CodeVersion::V3 {
+            unlocked_bytes = Self::unlock(passphrase, body)?;
+            &unlocked_bytes
+        } else {
+            body
+        };
+        if version == CodeVersion::V5 {
+            return Err(TranscodingError::Pack);
+        }
+        let postcard_bytes = Self::decompress(version, compressed_bytes)?;
+        let mut puzzle = Self::deserialize_version(version, &postcard_bytes)?;
+        if version == CodeVersion::V3 {
+            puzzle.locked_code = Some(locked_code.into());
+        }
+        Ok(puzzle)
     }
-    pub fn purple(&self) -> &ConnectionSet {
-        &self.purple
+
+    /// The version of layout the puzzle is written in and the bytes it's written as, which is
+    /// what [`Self::encode`] compresses
+    pub fn serialize_version(&self) -> (u8, Vec<u8>) {
+        match &self.seal {
+            Some(seal) => (SEALED_VERSION, seal.layout().to_vec()),
+            None => (CURRENT_VERSION, self.serialize()),
+        }
     }
 
-    fn debug() -> Self {
-        let yellow = YellowSet(ConnectionSet::new("Yellow", ["y"; 4]));
-        let blue = BlueSet(ConnectionSet::new("Blue", ["b"; 4]));
-        let purple = PurpleSet(ConnectionSet::new("Purple", ["p"; 4]));
-        let green = GreenSet(ConnectionSet::new("Green", ["g"; 4]));
-        Self {
-            yellow,
-            blue,
-            purple,
-            green,
+    /// Reads a puzzle written in the layout of `version`
+    pub fn deserialize_version(
+        version: CodeVersion,
+        postcard_bytes: &[u8],
+    ) -> Result<Self, TranscodingError> {
+        match version {
+            CodeVersion::Unversioned => Self::migrate_unversioned(postcard_bytes),
+            CodeVersion::V1 | CodeVersion::V2 | CodeVersion::V3 => {
+                Self::deserialize(postcard_bytes)
+            }
+            CodeVersion::V4 => Self::deserialize_sealed(postcard_bytes),
+            CodeVersion::V5 => Err(TranscodingError::Pack),
         }
     }
 
-    pub const fn empty() -> Self {
-        let yellow = YellowSet(ConnectionSet::empty_set());
-        let blue = BlueSet(ConnectionSet::empty_set());
-        let purple = PurpleSet(ConnectionSet::empty_set());
-        let green = GreenSet(ConnectionSet::empty_set());
+    /// Compresses a puzzle written in the layout of `version` into a code
+    pub fn code(version: u8, postcard_bytes: &[u8]) -> String {
+        let compressed_bytes = Self::compress(postcard_bytes);
+        Self::wrap_base64(&envelope::seal(version, &compressed_bytes))
+    }
 
-        Self {
-            yellow,
-            blue,
-            purple,
-            green,
+    fn lock_code(passphrase: &str, postcard_bytes: &[u8]) -> String {
+        let compressed_bytes = Self::compress(postcard_bytes);
+        let header = envelope::header(LOCKED_VERSION);
+        let locked_bytes = lock::lock(passphrase, &header, &compressed_bytes);
+        Self::wrap_base64(&envelope::seal(LOCKED_VERSION, &locked_bytes))
+    }
+
+    /// The words shuffled and each group sealed under them, see [`Self::encode_sealed`]
+    fn sealed_layout(&self) -> Vec<u8> {
+        let salt: [u8; SALT_LEN] = rand::random();
+        let groups = self
+            .sets
+            .iter()
+            .map(|set| {
+                let words: Vec<&str> = set.words_list_ref().collect();
+                let secret = postcard::to_allocvec(&set.secret()).expect("error serializing");
+                SealedGroup::new(&salt, &words, &secret)
+            })
+            .collect();
+        let mut words: Vec<String> = self.sets.iter().flat_map(|set| set.words.clone()).collect();
+        words.shuffle(&mut rand::thread_rng());
+        let layout = SealedLayout {
+            words,
+            group_size: self.group_size(),
+            rules: self.rules,
+            salt,
+            groups,
+            metadata: self.metadata.clone(),
+        };
+        postcard::to_allocvec(&layout).expect("error serializing")
+    }
+
+    /// Whether everything in the puzzle is within the [`limits`] codes are decoded under
+    pub fn within_limits(&self) -> Result<(), TranscodingError> {
+        limits::puzzle(&self.sets, &self.alternatives, &self.metadata)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let body = (&self.sets, self.rules, &self.alternatives);
+        let mut bytes = postcard::to_allocvec(&body).expect("error serializing");
+        if !self.metadata.is_empty() {
+            bytes.extend(postcard::to_allocvec(&self.metadata).expect("error serializing"));
         }
+        bytes
     }
 
-    pub fn new(
-        yellow: (&str, [&str; 4]),
-        blue: (&str, [&str; 4]),
-        purple: (&str, [&str; 4]),
-        green: (&str, [&str; 4]),
-    ) -> Self {
-        let yellow = YellowSet(ConnectionSet::new(yellow.0, yellow.1));
-        let blue = BlueSet(ConnectionSet::new(blue.0, blue.1));
-        let purple = PurpleSet(ConnectionSet::new(purple.0, purple.1));
-        let green = GreenSet(ConnectionSet::new(green.0, green.1));
+    /// Deflate against the built-in dictionary, see [`dictionary::compress`]
+    pub fn compress(postcard_bytes: &[u8]) -> Vec<u8> {
+        dictionary::compress(postcard_bytes)
+    }
 
+    /// How codes were compressed before version 2
+    pub fn gzip(postcard_bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(postcard_bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Base64 with a checksum in front, see [`checksum::add`]
+    pub fn wrap_base64(bytes: &[u8]) -> String {
+        checksum::add(&URL_SAFE.encode(bytes))
+    }
+
+    /// Checks the checksum if the code has one, which codes from before checksums don't
+    pub fn unwrap_base64(code: &str) -> Result<Vec<u8>, TranscodingError> {
+        limits::code(code)?;
+        let code = checksum::check(code)?;
+        URL_SAFE
+            .decode(code)
+            .map_err(|_| TranscodingError::NotACode)
+    }
+
+    /// Only locked codes have this stage, between the version and decompression
+    pub fn unlock(
+        passphrase: Option<&str>,
+        locked_bytes: &[u8],
+    ) -> Result<Vec<u8>, TranscodingError> {
+        let passphrase = passphrase.ok_or(TranscodingError::Passphrase)?;
+        lock::unlock(passphrase, &envelope::header(LOCKED_VERSION), locked_bytes)
+    }
+
+    pub fn decompress(
+        version: CodeVersion,
+        compressed_bytes: &[u8],
+    ) -> Result<Vec<u8>, TranscodingError> {
+        if !matches!(version, CodeVersion::Unversioned | CodeVersion::V1) {
+            return dictionary::decompress(compressed_bytes);
+        }
+        limits::decompressed(GzDecoder::new(compressed_bytes), 0)
+    }
+
+    /// Reads the layout of the current version, see [`CURRENT_VERSION`]. Anything over the
+    /// [`limits`] is turned away before the puzzle's built.
+    pub fn deserialize(postcard_bytes: &[u8]) -> Result<Self, TranscodingError> {
+        let ((sets, rules, alternatives), rest) =
+            postcard::take_from_bytes::<PuzzleBody>(postcard_bytes)
+                .map_err(|_| TranscodingError::Postcard)?;
+        let metadata = match rest {
+            [] => Metadata::new(),
+            rest => postcard::from_bytes(rest).map_err(|_| TranscodingError::Postcard)?,
+        };
+        let sets = PuzzleSets {
+            sets,
+            rules,
+            alternatives,
+            metadata,
+        };
+        sets.try_into().map_err(|error| match error {
+            ShapeError::Limit(error) => error,
+            _ => TranscodingError::Postcard,
+        })
+    }
+
+    /// A sealed code's words go into the groups in the order they're written, until guesses
+    /// sort them out
+    pub fn deserialize_sealed(postcard_bytes: &[u8]) -> Result<Self, TranscodingError> {
+        let layout: SealedLayout =
+            postcard::from_bytes(postcard_bytes).map_err(|_| TranscodingError::Postcard)?;
+        limits::sealed(&layout)?;
+        if layout.group_size == 0 || layout.words.len() != layout.groups.len() * layout.group_size {
+            return Err(TranscodingError::Postcard);
+        }
+        let sets = layout
+            .words
+            .chunks(layout.group_size)
+            .map(|words| {
+                let words: Vec<&str> = words.iter().map(String::as_str).collect();
+                ConnectionSet::new("", &words)
+            })
+            .collect();
+        let mut puzzle = Self::try_new(sets)
+            .map_err(|_| TranscodingError::Postcard)?
+            .with_rules(layout.rules)
+            .with_metadata(layout.metadata.clone());
+        puzzle.seal = Some(Seal::new(&layout, postcard_bytes));
+        Ok(puzzle)
+    }
+
+    /// Codes from before versioning were made either in the version 1 layout or, before
+    /// that, in the legacy one
+    pub fn migrate_unversioned(postcard_bytes: &[u8]) -> Result<Self, TranscodingError> {
+        // a code over the limits is turned away, not read again the older way
+        match Self::deserialize(postcard_bytes) {
+            Err(TranscodingError::Postcard) => {}
+            result => return result,
+        }
+        let puzzle: Self = postcard::from_bytes::<LegacyPuzzle>(postcard_bytes)
+            .map(Into::into)
+            .map_err(|_| TranscodingError::Postcard)?;
+        limits::puzzle(&puzzle.sets, &[], &puzzle.metadata)?;
+        Ok(puzzle)
+    }
+}
+
+impl ConnectionPuzzle {
+    fn debug() -> Self {
+        let sets = [
+            ConnectionSet::new("Yellow", &["y"; 4]),
+            ConnectionSet::new("Blue", &["b"; 4]),
+            ConnectionSet::new("Purple", &["p"; 4]),
+            ConnectionSet::new("Green", &["g"; 4]),
+        ];
+        Self::new(sets.into())
+    }
+
+    pub const fn empty() -> Self {
         Self {
-            yellow,
-            blue,
-            purple,
-            green,
+            sets: Vec::new(),
+            rules: Rules::STANDARD,
+            alternatives: Vec::new(),
+            metadata: Metadata::new(),
+            seal: None,
+            signer: None,
+            locked_code: None,
+        }
+    }
+
+    /// Panics if the sets don't make a valid board, see [`ConnectionPuzzle::try_new`]
+    pub fn new(sets: Vec<ConnectionSet>) -> Self {
+        match Self::try_new(sets) {
+            Ok(puzzle) => puzzle,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    /// Like [`ConnectionPuzzle::try_new`], but also turns away puzzles that couldn't be
+    /// played fairly, and lists everything wrong instead of the first thing
+    pub fn validated(sets: Vec<ConnectionSet>) -> Result<Self, ValidationError> {
+        validate(&sets)?;
+        Ok(Self::try_new(sets).expect("validation checks the shape"))
+    }
+
+    /// Only checks that the sets make a board. Codes are decoded through this so puzzles
+    /// shared before validation existed still open.
+    pub fn try_new(sets: Vec<ConnectionSet>) -> Result<Self, ShapeError> {
+        if !(MIN_GROUPS..=MAX_GROUPS).contains(&sets.len()) {
+            return Err(ShapeError::GroupCount(sets.len()));
+        }
+        let group_size = sets[0].len();
+        if !(MIN_GROUP_SIZE..=MAX_GROUP_SIZE).contains(&group_size) {
+            return Err(ShapeError::GroupSize(group_size));
         }
+        if sets.iter().any(|set| set.len() != group_size) {
+            return Err(ShapeError::Uneven);
+        }
+        Ok(Self {
+            sets,
+            rules: Rules::default(),
+            alternatives: Vec::new(),
+            metadata: Metadata::new(),
+            seal: None,
+            signer: None,
+            locked_code: None,
+        })
+    }
+
+    /// A checksum of everything in the puzzle, to tell whether something saved from a game
+    /// belongs to it
+    pub fn fingerprint(&self) -> u32 {
+        let mut crc = flate2::Crc::new();
+        // a sealed puzzle's themes fill in as it's played, so it goes by its code instead
+        match &self.seal {
+            Some(seal) => crc.update(seal.layout()),
+            None => crc.update(&self.serialize()),
+        }
+        crc.sum()
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// The name of the trusted key that signed the puzzle's code, if it came through
+    /// [`Self::decode_trusted`] with a good signature
+    pub fn signer(&self) -> Option<&str> {
+        self.signer.as_deref()
+    }
+
+    pub fn sets(&self) -> &[ConnectionSet] {
+        &self.sets
+    }
+
+    pub fn colors(&self) -> ColorIter {
+        ColorIter::new(self.num_groups())
     }
 
     pub fn theme(&self, reference: PuzzleRef) -> &str {
@@ -325,35 +891,257 @@ impl ConnectionPuzzle {
         &set[reference.word_index]
     }
 
+    pub fn has_group(&self, color: Color) -> bool {
+        color.index() < self.num_groups()
+    }
+
     pub fn by_color(&self, color: Color) -> &ConnectionSet {
-        match color {
-            Color::Blue => &self.blue,
-            Color::Green => &self.green,
-            Color::Purple => &self.purple,
-            Color::Yellow => &self.yellow,
+        &self.sets[color.index()]
+    }
+
+    /// What the group is called, which is its color unless the puzzle names it
+    pub fn label(&self, color: Color) -> &str {
+        self.by_color(color).custom_label().unwrap_or(color.name())
+    }
+
+    /// The `#rrggbb` color the group is shown in
+    pub fn hex(&self, color: Color) -> &str {
+        self.by_color(color).custom_hex().unwrap_or(color.hex())
+    }
+
+    /// How hard the group is meant to be, lower being easier
+    pub fn rank(&self, color: Color) -> u8 {
+        self.by_color(color)
+            .custom_rank()
+            .unwrap_or(color.default_rank())
+    }
+
+    /// The groups from easiest to hardest, in group order where they're ranked the same
+    pub fn by_difficulty(&self) -> Vec<Color> {
+        let mut colors: Vec<Color> = self.colors().collect();
+        colors.sort_by_key(|&color| self.rank(color));
+        colors
+    }
+
+    /// The group with `name` for a label, or failing that for a color
+    pub fn group_named(&self, name: &str) -> Option<Color> {
+        let name = name.trim();
+        self.colors()
+            .find(|&color| self.label(color).eq_ignore_ascii_case(name))
+            .or_else(|| Color::try_from(name).ok())
+            .filter(|&color| self.has_group(color))
+    }
+
+    pub fn by_label(&self, name: &str) -> Option<&ConnectionSet> {
+        self.group_named(name).map(|color| self.by_color(color))
+    }
+
+    /// Lets the puzzle also be solved with some cards in other groups
+    pub fn with_alternative(mut self, alternative: Alternative) -> Result<Self, ShapeError> {
+        if !alternative.fits(self.num_groups(), self.group_size())
+            || self.alternatives.contains(&alternative)
+        {
+            return Err(ShapeError::Alternative);
         }
+        self.alternatives.push(alternative);
+        Ok(self)
+    }
+
+    pub fn alternatives(&self) -> &[Alternative] {
+        &self.alternatives
+    }
+
+    /// The written solution and then every alternative
+    pub fn num_solutions(&self) -> usize {
+        self.alternatives.len() + 1
+    }
+
+    /// The group `card` belongs to in a solution, where solution 0 is the one written out
+    pub fn group_of(&self, solution: usize, card: PuzzleRef) -> Color {
+        if let Some(seal) = &self.seal {
+            return seal.group_of(card);
+        }
+        match solution.checked_sub(1) {
+            None => card.color(),
+            Some(index) => self.alternatives[index].group_of(card),
+        }
+    }
+
+    /// Tries `cards` as a group of a sealed puzzle. If they are one, the group is found: its
+    /// cards go into it and its theme and style are filled in.
+    pub fn unseal(&mut self, cards: &[PuzzleRef]) -> Option<Color> {
+        if !self.is_guess(cards) {
+            return None;
+        }
+        let words: Vec<&str> = cards
+            .iter()
+            .map(|card| card.word(&self.sets[card.color().index()]))
+            .collect();
+        let seal = self.seal.as_mut()?;
+        let (color, secret) = seal.secret(&words)?;
+        let secret: ConnectionSet = postcard::from_bytes(&secret).ok()?;
+        limits::group(&secret).ok()?;
+        seal.open(cards, color);
+        let set = &mut self.sets[color.index()];
+        set.theme = secret.theme;
+        set.style = secret.style;
+        self.tidy_labels();
+        Some(color)
+    }
+
+    /// Hides every group of a sealed puzzle again, as if none had been guessed
+    pub(super) fn reseal(&mut self) {
+        let Some(seal) = &self.seal else {
+            return;
+        };
+        let fresh = Self::deserialize_sealed(seal.layout()).expect("the layout was decoded before");
+        self.sets = fresh.sets;
+        self.seal = fresh.seal;
+    }
+
+    /// Whether `cards` are one of a sealed puzzle's groups, without finding it
+    pub fn is_sealed_group(&self, cards: &[PuzzleRef]) -> bool {
+        let Some(seal) = &self.seal else {
+            return false;
+        };
+        if !self.is_guess(cards) {
+            return false;
+        }
+        let words: Vec<&str> = cards.iter().map(|&card| &self[card]).collect();
+        seal.find(&words).is_some()
+    }
+
+    /// Whether every red herring is a word of its group and points at a group of the puzzle,
+    /// since finding the decoy's theme would go out of bounds otherwise
+    fn herrings_fit(&self) -> bool {
+        self.sets.iter().all(|set| {
+            set.herrings.iter().all(|herring| {
+                herring.word_index < self.group_size() && herring.decoy.index() < self.num_groups()
+            })
+        })
+    }
+
+    /// Codes aren't validated, so a label that's blank once it's trimmed, or that another group
+    /// already goes by, is dropped and the group goes by its color
+    fn tidy_labels(&mut self) {
+        for index in 0..self.sets.len() {
+            let label = self.sets[index].style.label.take();
+            let label = label.map(|label| label.trim().to_string());
+            let taken = |label: &str| {
+                self.colors().any(|color| {
+                    color.index() != index && self.label(color).trim().eq_ignore_ascii_case(label)
+                })
+            };
+            let keep = label
+                .as_deref()
+                .is_some_and(|label| !label.is_empty() && !taken(label));
+            if keep {
+                self.sets[index].style.label = label;
+            }
+        }
+    }
+
+    /// Whether `cards` are as many different cards of this puzzle as make a group
+    fn is_guess(&self, cards: &[PuzzleRef]) -> bool {
+        cards.len() == self.group_size()
+            && cards.iter().enumerate().all(|(index, card)| {
+                card.color().index() < self.num_groups()
+                    && card.word_index() < self.group_size()
+                    && !cards[..index].contains(card)
+            })
     }
 }
 
-#[wasm_bindgen]
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
 pub enum TranscodingError {
-    #[error("couldn't decode")]
-    Base64,
-    #[error("couldn't decompress")]
-    Gzip,
-    #[error("couldn't deserialize")]
+    #[error("that isn't a puzzle code")]
+    NotACode,
+    #[error("the code is cut short: it should be {expected} characters long, but it's {found}")]
+    Truncated { expected: usize, found: usize },
+    #[error("the code has a typo at character {position}")]
+    Typo { position: usize },
+    #[error("the code is missing a character at {position}")]
+    Missing { position: usize },
+    #[error("the code has an extra character at {position}")]
+    Extra { position: usize },
+    #[error("the code has typos, too many to say where")]
+    Mistyped,
+    /// The code came through intact but what's in it doesn't decompress
+    #[error("the code is corrupt: it doesn't decompress")]
+    Decompress,
+    /// The code came through intact but what's in it isn't a puzzle
+    #[error("the code is corrupt: there's no puzzle in it")]
     Postcard,
+    #[error("the code is version {0}, but only codes up to version {LATEST_VERSION} can be read")]
+    UnsupportedVersion(u8),
+    #[error("the code is locked, and the passphrase is missing or wrong")]
+    Passphrase,
+    #[error("the code's signature is malformed")]
+    Signature,
+    #[error("the code is for a pack of puzzles, not a single one")]
+    Pack,
+    #[error("a sealed puzzle can't be locked")]
+    Sealed,
+    #[error("a locked puzzle can't go in a pack")]
+    Locked,
+    #[error("a pack needs at least one puzzle")]
+    EmptyPack,
+    #[error("the code is too long to be a puzzle, at over {limit} bytes")]
+    CodeTooLong { limit: usize },
+    #[error("the code unpacks to more than {limit} bytes, which is more than any puzzle")]
+    TooLarge { limit: usize },
+    #[error("the {field} is over {limit} bytes long")]
+    TooLong { field: &'static str, limit: usize },
+    #[error("the code has more than {limit} {field}")]
+    TooMany { field: &'static str, limit: usize },
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg(feature = "wasm")]
+impl From<TranscodingError> for JsValue {
+    /// The error's `name` says what went wrong, so the site can word its own message, and a
+    /// typo's `position` is on it too
+    fn from(error: TranscodingError) -> Self {
+        let js_error = js_sys::Error::new(&error.to_string());
+        js_error.set_name(match error {
+            TranscodingError::NotACode => "NotACode",
+            TranscodingError::Truncated { .. } => "Truncated",
+            TranscodingError::Typo { .. } => "Typo",
+            TranscodingError::Missing { .. } => "Missing",
+            TranscodingError::Extra { .. } => "Extra",
+            TranscodingError::Mistyped => "Mistyped",
+            TranscodingError::Decompress
+            | TranscodingError::Postcard
+            | TranscodingError::EmptyPack => "Corrupt",
+            TranscodingError::UnsupportedVersion(_) => "UnsupportedVersion",
+            TranscodingError::Passphrase => "Passphrase",
+            TranscodingError::Signature => "Signature",
+            TranscodingError::Pack => "Pack",
+            TranscodingError::Sealed => "Sealed",
+            TranscodingError::Locked => "Locked",
+            TranscodingError::CodeTooLong { .. }
+            | TranscodingError::TooLarge { .. }
+            | TranscodingError::TooLong { .. }
+            | TranscodingError::TooMany { .. } => "TooLarge",
+        });
+        if let TranscodingError::Typo { position }
+        | TranscodingError::Missing { position }
+        | TranscodingError::Extra { position } = error
+        {
+            // setting a property on a fresh error object can't fail
+            let _ = js_sys::Reflect::set(&js_error, &"position".into(), &position.into());
+        }
+        js_error.into()
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct PuzzleRef {
     color: Color,
     word_index: usize,
 }
 
 impl PuzzleRef {
-    const fn new(color: Color, word_index: usize) -> Self {
+    pub(super) const fn new(color: Color, word_index: usize) -> Self {
         Self { color, word_index }
     }
 
@@ -361,58 +1149,254 @@ impl PuzzleRef {
         self.color
     }
 
+    pub const fn word_index(&self) -> usize {
+        self.word_index
+    }
+
     pub fn word<'a>(&self, set: &'a ConnectionSet) -> &'a str {
         set.words[self.word_index].as_ref()
     }
 
-    pub const fn new_set() -> [Self; 16] {
-        use Color::*;
-        [
-            Self::new(Yellow, 0),
-            Self::new(Yellow, 1),
-            Self::new(Yellow, 2),
-            Self::new(Yellow, 3),
-            Self::new(Blue, 0),
-            Self::new(Blue, 1),
-            Self::new(Blue, 2),
-            Self::new(Blue, 3),
-            Self::new(Green, 0),
-            Self::new(Green, 1),
-            Self::new(Green, 2),
-            Self::new(Green, 3),
-            Self::new(Purple, 0),
-            Self::new(Purple, 1),
-            Self::new(Purple, 2),
-            Self::new(Purple, 3),
-        ]
+    /// One reference for every card on a board of `num_groups` groups of `group_size` words
+    pub fn new_set(num_groups: usize, group_size: usize) -> Vec<Self> {
+        ColorIter::new(num_groups)
+            .flat_map(|color| (0..group_size).map(move |word_index| Self::new(color, word_index)))
+            .collect()
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-pub struct PuzzleKey {
-    pub color: Color,
-    pub word_index: usize,
-}
-
-impl Default for PuzzleKey {
+impl Default for ConnectionPuzzle {
     fn default() -> Self {
-        Self {
-            color: Color::Yellow,
-            word_index: 0,
-        }
+        let purple = ConnectionSet::new("___Room", &["war", "bed", "situation", "clean"])
+            .with_hints(&["Places in a house", "Add a word"])
+            .with_herring(0, Color::Green);
+        let green = ConnectionSet::new(
+            "Domains of Greek Gods",
+            &["victory", "ocean", "thunder", "music"],
+        )
+        .with_hints(&["Mythology"]);
+        let yellow =
+            ConnectionSet::new("Minecraft Cake Recipe", &["wheat", "milk", "eggs", "sugar"])
+                .with_hints(&["Baking", "Video games"]);
+        let blue = ConnectionSet::new("noble gasses", &["helium", "argon", "krypton", "neon"])
+            .with_hints(&["Chemistry"]);
+
+        Self::new(vec![yellow, blue, purple, green])
     }
 }
 
-impl Default for ConnectionPuzzle {
-    fn default() -> Self {
-        let purple = ("___Room", ["war", "bed", "situation", "clean"]);
-        let green = (
-            "Domains of Greek Gods",
-            ["victory", "ocean", "thunder", "music"],
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::pack::PuzzlePack;
+    use crate::game::validate::{Problem, ProblemKind};
+
+    /// Made by the first release, with four fixed groups and no version
+    const LEGACY_CODE: &str = "H4sIAAAAAAAC_w3NwQ3CMBAEwIfPoTM-fHjQwCZaZCvOxbq9INE9VDB272q2QrLZd9qGmKZz8PYccKbsQKh-6JcqEdkWIa9we_CrqtbfWenJKIl1oTZM1leQKif2wnEU_IXZnT9RSbsJbAAAAA==";
+    /// The same puzzle in a version 1 code, gzip with a checksum
+    const V1_CODE: &str = "uABvO4GoT1-1~Q04BH4sIAAAAAAAC_yWNMQ7CMBRDE8UpEjNnYmFh4AK_1UeJmqZR_IvE7SmtBy-23gPumQkYhUTLs2KS3sC1qPvn8ixS1Qgs0hk_WjdGlW5poNjW6_HCQ79EZMpvi1pNezAZB-Uk7eTEV1clwipz0LIE2aUt13P08N65q7vt_QNGUctXkQAAAA==";
+
+    fn fixture() -> ConnectionPuzzle {
+        ConnectionPuzzle::new(vec![
+            ConnectionSet::new("Fish", &["bass", "pike", "carp", "sole"]),
+            ConnectionSet::new("Planets", &["mars", "venus", "earth", "saturn"]),
+            ConnectionSet::new("Keys", &["shift", "enter", "tab", "escape"]),
+            ConnectionSet::new("Trees", &["oak", "elm", "ash", "pine"]),
+        ])
+    }
+
+    fn cards_of(puzzle: &ConnectionPuzzle, words: &[&str]) -> Vec<PuzzleRef> {
+        PuzzleRef::new_set(puzzle.num_groups(), puzzle.group_size())
+            .into_iter()
+            .filter(|&card| words.contains(&&puzzle[card]))
+            .collect()
+    }
+
+    #[test]
+    fn missing_groups_are_none() {
+        let puzzle = ConnectionPuzzle::new(fixture().sets[..3].to_vec());
+        assert_eq!(puzzle.green_owned(), None);
+        assert_eq!(puzzle.set_owned(9), None);
+        assert_eq!(puzzle.purple_owned(), Some(fixture().sets[2].clone()));
+    }
+
+    #[test]
+    fn styles_of_missing_groups_are_blank() {
+        let puzzle = ConnectionPuzzle::new(fixture().sets[..3].to_vec());
+        assert_eq!(puzzle.group_label(Color::Orange), "");
+        assert_eq!(puzzle.group_hex(Color::Green), "");
+        assert_eq!(puzzle.group_rank(Color::Green), 0);
+        assert_eq!(puzzle.group_label(Color::Purple), "purple");
+    }
+
+    #[test]
+    fn legacy_code_decodes() {
+        let puzzle = ConnectionPuzzle::decode(LEGACY_CODE).unwrap();
+        assert_eq!(puzzle.serialize(), fixture().serialize());
+    }
+
+    #[test]
+    fn version_1_code_decodes() {
+        let puzzle = ConnectionPuzzle::decode(V1_CODE).unwrap();
+        assert_eq!(puzzle.serialize(), fixture().serialize());
+    }
+
+    #[test]
+    fn code_round_trips() {
+        let puzzle =
+            ConnectionPuzzle::default().with_metadata(Metadata::new().with_title("Kitchen sink"));
+        let code = puzzle.encode();
+        assert_eq!(
+            ConnectionPuzzle::decode(&code).unwrap().serialize(),
+            puzzle.serialize()
+        );
+    }
+
+    #[test]
+    fn locked_code_needs_its_passphrase() {
+        let code = fixture().encode_locked("hunter2").unwrap();
+        assert!(ConnectionPuzzle::is_locked(&code));
+        let puzzle = ConnectionPuzzle::decode_locked(&code, "hunter2").unwrap();
+        assert_eq!(puzzle.serialize(), fixture().serialize());
+        assert_eq!(
+            ConnectionPuzzle::decode_locked(&code, "hunter3").unwrap_err(),
+            TranscodingError::Passphrase
+        );
+        assert_eq!(
+            ConnectionPuzzle::decode(&code).unwrap_err(),
+            TranscodingError::Passphrase
+        );
+    }
+
+    #[test]
+    fn locked_puzzle_shares_its_locked_code() {
+        let code = fixture().encode_locked("hunter2").unwrap();
+        let puzzle = ConnectionPuzzle::decode_locked(&code, "hunter2").unwrap();
+        assert!(puzzle.came_locked());
+        assert_eq!(puzzle.share_code(), code);
+        assert!(!fixture().came_locked());
+        assert_eq!(fixture().share_code(), fixture().encode());
+    }
+
+    #[test]
+    fn guessing_a_sealed_group_reveals_its_theme() {
+        let code = fixture().encode_sealed();
+        let mut puzzle = ConnectionPuzzle::decode(&code).unwrap();
+        assert!(puzzle.is_sealed());
+        assert!(puzzle.sets().iter().all(|set| set.theme_ref().is_empty()));
+
+        let wrong = cards_of(&puzzle, &["bass", "pike", "carp", "oak"]);
+        assert_eq!(puzzle.unseal(&wrong), None);
+
+        let fish = cards_of(&puzzle, &["bass", "pike", "carp", "sole"]);
+        let color = puzzle.unseal(&fish).unwrap();
+        assert!(puzzle.is_found(color));
+        assert_eq!(puzzle.by_color(color).theme_ref(), "Fish");
+        let others = puzzle.colors().filter(|&other| other != color);
+        assert!(others.into_iter().all(|other| !puzzle.is_found(other)));
+    }
+
+    #[test]
+    fn sealed_puzzles_cant_be_locked() {
+        let sealed = ConnectionPuzzle::decode(&fixture().encode_sealed()).unwrap();
+        assert_eq!(
+            sealed.encode_locked("hunter2").unwrap_err(),
+            TranscodingError::Sealed
         );
-        let yellow = ("Minecraft Cake Recipe", ["wheat", "milk", "eggs", "sugar"]);
-        let blue = ("noble gasses", ["helium", "argon", "krypton", "neon"]);
+        assert_eq!(
+            sealed.try_encode_locked("hunter2").unwrap_err(),
+            TranscodingError::Sealed
+        );
+    }
+
+    #[test]
+    fn gzip_bomb_stops_at_the_limit() {
+        let bomb = ConnectionPuzzle::gzip(&vec![0; 100 * limits::MAX_DECOMPRESSED_LEN]);
+        let code = ConnectionPuzzle::wrap_base64(&envelope::seal(1, &bomb));
+        assert_eq!(
+            ConnectionPuzzle::decode(&code).unwrap_err(),
+            TranscodingError::TooLarge {
+                limit: limits::MAX_DECOMPRESSED_LEN
+            }
+        );
+    }
+
+    /// Text that won't compress, so its code is as long as it can be
+    fn noise(seed: u64, len: usize) -> String {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let alphabet = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+                alphabet[(state >> 33) as usize % alphabet.len()] as char
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validation_turns_away_what_wont_decode() {
+        let long = "a".repeat(limits::MAX_TEXT_LEN + 1);
+        let mut sets = fixture().sets;
+        sets[1] = ConnectionSet::new("Planets", &["mars", &long, "earth", "saturn"]);
+        sets[2] = ConnectionSet::new(&long, &["shift", "enter", "tab", "escape"]);
+        let error = ConnectionPuzzle::validated(sets).unwrap_err();
+        let kinds: Vec<_> = error.problems_ref().iter().map(Problem::kind).collect();
+        assert_eq!(kinds, [ProblemKind::TooLong, ProblemKind::TooLong]);
+        assert_eq!(error.problems_ref()[0].word_index(), Some(1));
+        assert_eq!(error.problems_ref()[1].word_index(), None);
+    }
 
-        Self::new(yellow, blue, purple, green)
+    #[test]
+    fn try_encode_turns_away_what_wont_decode() {
+        let intro = "a".repeat(limits::MAX_NOTE_LEN + 1);
+        let puzzle = fixture().with_metadata(Metadata::new().with_intro(&intro));
+        let error = TranscodingError::TooLong {
+            field: "intro",
+            limit: limits::MAX_NOTE_LEN,
+        };
+        assert_eq!(puzzle.try_encode().unwrap_err(), error);
+        assert_eq!(puzzle.try_encode_sealed().unwrap_err(), error);
+        assert_eq!(puzzle.try_encode_locked("hunter2").unwrap_err(), error);
+
+        let code = fixture().try_encode().unwrap();
+        assert_eq!(code, fixture().encode());
+    }
+
+    #[test]
+    fn try_encode_turns_away_codes_too_long_to_decode() {
+        let biggest = |seed: u64| {
+            let sets = (0..MAX_GROUPS as u64)
+                .map(|group| {
+                    let seed = seed * 1000 + group * 100;
+                    let words: Vec<String> = (1..=MAX_GROUP_SIZE as u64)
+                        .map(|word| noise(seed + word, limits::MAX_TEXT_LEN))
+                        .collect();
+                    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+                    ConnectionSet::new(&noise(seed, limits::MAX_TEXT_LEN), &words)
+                })
+                .collect();
+            ConnectionPuzzle::validated(sets).unwrap()
+        };
+        assert!(biggest(0).try_encode().is_ok());
+
+        let pack = |len: u64| {
+            (0..len).fold(PuzzlePack::new("Noise"), |pack, seed| {
+                pack.with_puzzle(biggest(seed)).unwrap()
+            })
+        };
+        assert_eq!(
+            pack(4).try_encode().unwrap_err(),
+            TranscodingError::CodeTooLong {
+                limit: limits::MAX_CODE_LEN
+            }
+        );
+        assert_eq!(
+            pack(6).try_encode().unwrap_err(),
+            TranscodingError::TooLarge {
+                limit: limits::MAX_DECOMPRESSED_LEN
+            }
+        );
     }
 }
@@ -0,0 +1,137 @@
+use super::hints::{HintCost, HintKind};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// How a game is played. Puzzles carry the rules their author picked, and a
+/// `GameState` can be started with different ones.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// `None` means the game can't be lost
+    mistake_limit: Option<u8>,
+    one_away_feedback: bool,
+    block_repeats: bool,
+    /// Lets submitted guesses be undone, not just selections and shuffles
+    practice: bool,
+    nudge_cost: HintCost,
+    word_cost: HintCost,
+    group_cost: HintCost,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl Rules {
+    pub const STANDARD: Self = Self {
+        mistake_limit: Some(4),
+        one_away_feedback: true,
+        block_repeats: true,
+        practice: false,
+        nudge_cost: HintCost::new(0, 10),
+        word_cost: HintCost::new(0, 20),
+        group_cost: HintCost::new(1, 0),
+    };
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Rules {
+    /// Four mistakes, with one-away hints and repeat guesses blocked, like the NYT game
+    pub fn standard() -> Self {
+        Self::STANDARD
+    }
+
+    /// No mistake limit
+    pub fn zen() -> Self {
+        Self {
+            mistake_limit: None,
+            ..Self::STANDARD
+        }
+    }
+
+    /// The first mistake loses
+    pub fn sudden_death() -> Self {
+        Self {
+            mistake_limit: Some(1),
+            ..Self::STANDARD
+        }
+    }
+
+    /// Standard rules, but any guess can be taken back
+    pub fn practice() -> Self {
+        Self {
+            practice: true,
+            ..Self::STANDARD
+        }
+    }
+
+    /// A limit of zero still allows the one mistake that loses the game
+    pub fn with_mistake_limit(self, mistake_limit: Option<u8>) -> Self {
+        Self {
+            mistake_limit: mistake_limit.map(|limit| limit.max(1)),
+            ..self
+        }
+    }
+
+    pub fn with_one_away_feedback(self, one_away_feedback: bool) -> Self {
+        Self {
+            one_away_feedback,
+            ..self
+        }
+    }
+
+    pub fn with_repeats_blocked(self, block_repeats: bool) -> Self {
+        Self {
+            block_repeats,
+            ..self
+        }
+    }
+
+    pub fn with_practice(self, practice: bool) -> Self {
+        Self { practice, ..self }
+    }
+
+    pub fn with_hint_cost(self, kind: HintKind, cost: HintCost) -> Self {
+        match kind {
+            HintKind::Nudge => Self {
+                nudge_cost: cost,
+                ..self
+            },
+            HintKind::Word => Self {
+                word_cost: cost,
+                ..self
+            },
+            HintKind::Group => Self {
+                group_cost: cost,
+                ..self
+            },
+        }
+    }
+
+    pub fn mistake_limit(&self) -> Option<u8> {
+        self.mistake_limit
+    }
+
+    pub fn one_away_feedback(&self) -> bool {
+        self.one_away_feedback
+    }
+
+    pub fn block_repeats(&self) -> bool {
+        self.block_repeats
+    }
+
+    pub fn is_practice(&self) -> bool {
+        self.practice
+    }
+
+    pub fn hint_cost(&self, kind: HintKind) -> HintCost {
+        match kind {
+            HintKind::Nudge => self.nudge_cost,
+            HintKind::Word => self.word_cost,
+            HintKind::Group => self.group_cost,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use super::board::Selection;
+use super::puzzle::ConnectionPuzzle;
+use super::rules::Rules;
+use super::Snapshot;
+use base64::{engine::general_purpose::URL_SAFE, Engine as _};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A game in progress, without the puzzle it's being played on. Resuming needs the same puzzle,
+/// which is checked with its fingerprint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveState {
+    puzzle: u32,
+    rules: Rules,
+    seed: u64,
+    state: Snapshot,
+}
+
+impl SaveState {
+    pub(super) fn new(puzzle: &ConnectionPuzzle, rules: Rules, seed: u64, state: Snapshot) -> Self {
+        Self {
+            puzzle: puzzle.fingerprint(),
+            rules,
+            seed,
+            state,
+        }
+    }
+
+    /// Saves are small enough that compressing them wouldn't make the code any shorter
+    pub fn encode(&self) -> String {
+        let bytes = postcard::to_allocvec(self).expect("error serializing");
+        URL_SAFE.encode(bytes)
+    }
+
+    pub fn decode(code: &str) -> Result<Self, SaveError> {
+        let bytes = URL_SAFE.decode(code).map_err(|_| SaveError::Encoding)?;
+        postcard::from_bytes(&bytes).map_err(|_| SaveError::Encoding)
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub(super) fn guesses(&self) -> &[Selection] {
+        &self.state.guesses
+    }
+
+    /// Hands back the saved game if it could have been played on `puzzle`
+    pub(super) fn check(self, puzzle: &ConnectionPuzzle) -> Result<Snapshot, SaveError> {
+        if self.puzzle != puzzle.fingerprint() {
+            return Err(SaveError::WrongPuzzle);
+        }
+
+        let state = &self.state;
+        let board = &state.board;
+        let full_guesses = state.guesses.iter().all(|guess| {
+            guess.len() == puzzle.group_size() && board.is_selection_for(guess, puzzle)
+        });
+        let known_hints = state
+            .hints
+            .iter()
+            .all(|hint| hint.color.index() < puzzle.num_groups());
+        let within_limit = self
+            .rules
+            .mistake_limit()
+            .is_none_or(|limit| state.mistakes <= limit);
+
+        if board.fits(puzzle)
+            && full_guesses
+            && known_hints
+            && within_limit
+            && usize::from(state.successes) == board.num_matched()
+        {
+            Ok(self.state)
+        } else {
+            Err(SaveError::InvalidState)
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum SaveError {
+    #[error("couldn't read the save")]
+    Encoding,
+    #[error("the save is for a different puzzle")]
+    WrongPuzzle,
+    #[error("the save isn't a game that could have been played")]
+    InvalidState,
+}
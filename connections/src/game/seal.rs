@@ -0,0 +1,152 @@
+use super::color::Color;
+use super::metadata::Metadata;
+use super::puzzle::PuzzleRef;
+use super::rules::Rules;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const SALT_LEN: usize = 16;
+
+/// How a sealed puzzle is laid out in a code. The words are all there, shuffled, but which
+/// group each one is in only shows up by guessing a group right.
+///
+/// This keeps a shared link from spoiling the puzzle at a glance. It doesn't stop anyone
+/// determined, since a board only has so many ways of picking a group to try.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SealedLayout {
+    pub words: Vec<String>,
+    pub group_size: usize,
+    pub rules: Rules,
+    pub salt: [u8; SALT_LEN],
+    pub groups: Vec<SealedGroup>,
+    pub metadata: Metadata,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SealedGroup {
+    hash: [u8; 32],
+    /// The theme and style, encrypted under a key made from the group's words
+    secret: Vec<u8>,
+}
+
+impl SealedGroup {
+    pub fn new(salt: &[u8], words: &[&str], secret: &[u8]) -> Self {
+        let secret = cipher(salt, words)
+            .encrypt(&Nonce::default(), secret)
+            .expect("error encrypting");
+        Self {
+            hash: digest(salt, b"group", words),
+            secret,
+        }
+    }
+
+    fn open(&self, salt: &[u8], words: &[&str]) -> Option<Vec<u8>> {
+        cipher(salt, words)
+            .decrypt(&Nonce::default(), self.secret.as_slice())
+            .ok()
+    }
+}
+
+/// The groups of a sealed puzzle, and which group each card is in as far as anyone knows.
+/// Found groups hold exactly their own cards, and the rest are split up any old way.
+#[derive(Debug, Clone)]
+pub struct Seal {
+    salt: [u8; SALT_LEN],
+    groups: Vec<SealedGroup>,
+    group_size: usize,
+    placement: Vec<Color>,
+    found: Vec<bool>,
+    /// The code's postcard bytes, so the puzzle can be encoded again as it was
+    layout: Vec<u8>,
+}
+
+impl Seal {
+    pub fn new(layout: &SealedLayout, layout_bytes: &[u8]) -> Self {
+        let placement = PuzzleRef::new_set(layout.groups.len(), layout.group_size)
+            .into_iter()
+            .map(|card| card.color())
+            .collect();
+        Self {
+            salt: layout.salt,
+            groups: layout.groups.clone(),
+            group_size: layout.group_size,
+            placement,
+            found: vec![false; layout.groups.len()],
+            layout: layout_bytes.to_vec(),
+        }
+    }
+
+    pub fn layout(&self) -> &[u8] {
+        &self.layout
+    }
+
+    pub fn group_of(&self, card: PuzzleRef) -> Color {
+        self.placement[self.position(card)]
+    }
+
+    /// Whether the group has been guessed, and so whether [`Self::group_of`] is more than a
+    /// placeholder for its cards
+    pub fn is_found(&self, color: Color) -> bool {
+        self.found[color.index()]
+    }
+
+    /// The group that's exactly `words`, if there is one
+    pub fn find(&self, words: &[&str]) -> Option<Color> {
+        let hash = digest(&self.salt, b"group", words);
+        let index = self.groups.iter().position(|group| group.hash == hash)?;
+        Color::from_int(index as u8)
+    }
+
+    /// The group that's exactly `words` and its secret, which only they can decrypt
+    pub fn secret(&self, words: &[&str]) -> Option<(Color, Vec<u8>)> {
+        let color = self.find(words)?;
+        let secret = self.groups[color.index()].open(&self.salt, words)?;
+        Some((color, secret))
+    }
+
+    /// Marks the group found and moves `cards`, which are its cards, into it
+    pub fn open(&mut self, cards: &[PuzzleRef], color: Color) {
+        for &card in cards {
+            let here = self.position(card);
+            if self.placement[here] == color {
+                continue;
+            }
+            // trade places with a card that was only standing in for the group
+            let stand_in = (0..self.placement.len())
+                .find(|&there| {
+                    self.placement[there] == color
+                        && !cards.iter().any(|&card| self.position(card) == there)
+                })
+                .expect("groups are all the same size");
+            self.placement.swap(here, stand_in);
+        }
+        self.found[color.index()] = true;
+    }
+
+    fn position(&self, card: PuzzleRef) -> usize {
+        card.color().index() * self.group_size + card.word_index()
+    }
+}
+
+/// A group's words in any order give the same digest, and `purpose` keeps the hash anyone
+/// can read apart from the key
+fn digest(salt: &[u8], purpose: &[u8], words: &[&str]) -> [u8; 32] {
+    let mut words = words.to_vec();
+    words.sort_unstable();
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(purpose);
+    for word in words {
+        hasher.update((word.len() as u64).to_le_bytes());
+        hasher.update(word);
+    }
+    hasher.finalize().into()
+}
+
+/// Every group has its own key, so the same nonce never goes with the same key twice
+fn cipher(salt: &[u8], words: &[&str]) -> ChaCha20Poly1305 {
+    let key = digest(salt, b"theme", words);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
@@ -0,0 +1,162 @@
+use super::limits;
+use super::puzzle::{ConnectionPuzzle, TranscodingError};
+use base64::{engine::general_purpose::URL_SAFE, Engine as _};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use thiserror::Error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Goes between a code and its signature. It isn't in the base64 alphabet, so codes never
+/// have one of their own.
+pub const SEPARATOR: char = '.';
+
+/// The public keys whose signatures mark a puzzle as official, each with the name to credit
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    keys: Vec<(String, VerifyingKey)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Keyring {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts `public_key`, written the way [`public_key`] writes it, as `name`
+    pub fn trust(mut self, name: &str, public_key: &str) -> Result<Self, KeyError> {
+        let bytes = decode_key(public_key)?;
+        let key = VerifyingKey::from_bytes(&bytes).map_err(|_| KeyError::Invalid)?;
+        self.keys.push((name.into(), key));
+        Ok(self)
+    }
+}
+
+impl Keyring {
+    /// Who signed `code`, if it's signed by a key on the ring. A signature from anyone else,
+    /// or one that doesn't match the code, leaves it unverified.
+    pub fn signer(&self, code: &str) -> Result<Option<&str>, TranscodingError> {
+        limits::code(code)?;
+        let Some((unsigned, signature)) = code.split_once(SEPARATOR) else {
+            return Ok(None);
+        };
+        let bytes = ConnectionPuzzle::unwrap_base64(unsigned)?;
+        let signature = URL_SAFE
+            .decode(signature)
+            .ok()
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .ok_or(TranscodingError::Signature)?;
+        let signature = Signature::from_bytes(&signature);
+        Ok(self
+            .keys
+            .iter()
+            .find(|(_, key)| key.verify_strict(&bytes, &signature).is_ok())
+            .map(|(name, _)| name.as_str()))
+    }
+}
+
+/// A new secret key, to keep wherever official puzzles are made
+pub fn generate_key() -> String {
+    URL_SAFE.encode(rand::random::<[u8; 32]>())
+}
+
+/// The public key that goes with `secret_key`, to hand to players' [`Keyring`]s
+pub fn public_key(secret_key: &str) -> Result<String, KeyError> {
+    let key = SigningKey::from_bytes(&decode_key(secret_key)?);
+    Ok(URL_SAFE.encode(key.verifying_key().as_bytes()))
+}
+
+/// Appends a signature of the puzzle bytes in `code` to it, so it's the puzzle that's vouched
+/// for and not how the code happens to be written. Anything already signed is signed over again.
+pub fn sign(code: &str, secret_key: &str) -> Result<String, KeyError> {
+    let key = SigningKey::from_bytes(&decode_key(secret_key)?);
+    let code = unsigned(code);
+    let bytes = ConnectionPuzzle::unwrap_base64(code).map_err(|_| KeyError::NotACode)?;
+    let signature = key.sign(&bytes);
+    Ok(format!(
+        "{code}{SEPARATOR}{}",
+        URL_SAFE.encode(signature.to_bytes())
+    ))
+}
+
+/// The code without any signature on the end
+pub fn unsigned(code: &str) -> &str {
+    code.split_once(SEPARATOR).map_or(code, |(code, _)| code)
+}
+
+fn decode_key(key: &str) -> Result<[u8; 32], KeyError> {
+    let bytes = URL_SAFE.decode(key.trim()).map_err(|_| KeyError::Invalid)?;
+    bytes.try_into().map_err(|_| KeyError::Invalid)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum KeyError {
+    #[error("that isn't an Ed25519 key")]
+    Invalid,
+    #[error("only puzzle codes can be signed")]
+    NotACode,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_code_is_verified() {
+        let secret_key = generate_key();
+        let keyring = Keyring::new()
+            .trust("The Daily", &public_key(&secret_key).unwrap())
+            .unwrap();
+        let code = sign(&ConnectionPuzzle::default().encode(), &secret_key).unwrap();
+        let puzzle = ConnectionPuzzle::decode_trusted(&code, &keyring).unwrap();
+        assert_eq!(puzzle.signer(), Some("The Daily"));
+    }
+
+    #[test]
+    fn someone_else_signing_is_not_verified() {
+        let keyring = Keyring::new()
+            .trust("The Daily", &public_key(&generate_key()).unwrap())
+            .unwrap();
+        let code = sign(&ConnectionPuzzle::default().encode(), &generate_key()).unwrap();
+        let puzzle = ConnectionPuzzle::decode_trusted(&code, &keyring).unwrap();
+        assert_eq!(puzzle.signer(), None);
+    }
+
+    #[test]
+    fn tampered_code_is_not_verified() {
+        let secret_key = generate_key();
+        let keyring = Keyring::new()
+            .trust("The Daily", &public_key(&secret_key).unwrap())
+            .unwrap();
+        let code = sign(&ConnectionPuzzle::default().encode(), &secret_key).unwrap();
+        let (_, signature) = code.split_once(SEPARATOR).unwrap();
+        let other = ConnectionPuzzle::decode("debug").unwrap().encode();
+        let forged = format!("{other}{SEPARATOR}{signature}");
+        assert_eq!(keyring.signer(&forged), Ok(None));
+        assert_eq!(
+            keyring.signer(&format!("{other}{SEPARATOR}nonsense")),
+            Err(TranscodingError::Signature)
+        );
+    }
+
+    #[test]
+    fn locked_code_is_verified_once_unlocked() {
+        let secret_key = generate_key();
+        let keyring = Keyring::new()
+            .trust("The Daily", &public_key(&secret_key).unwrap())
+            .unwrap();
+        let locked = ConnectionPuzzle::default()
+            .encode_locked("hunter2")
+            .unwrap();
+        let code = sign(&locked, &secret_key).unwrap();
+        let puzzle = ConnectionPuzzle::decode_locked_trusted(&code, "hunter2", &keyring).unwrap();
+        assert_eq!(puzzle.signer(), Some("The Daily"));
+    }
+
+    #[test]
+    fn only_codes_are_signed() {
+        assert_eq!(sign("debug", &generate_key()), Err(KeyError::NotACode));
+    }
+}
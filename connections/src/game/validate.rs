@@ -0,0 +1,327 @@
+use super::color::{Color, MAX_GROUPS};
+use super::limits::{MAX_HINTS, MAX_TEXT_LEN};
+use super::puzzle::{ConnectionSet, MAX_GROUP_SIZE, MIN_GROUPS, MIN_GROUP_SIZE};
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProblemKind {
+    GroupCount,
+    GroupSize,
+    Uneven,
+    EmptyTheme,
+    BlankWord,
+    /// The same word in two groups can't be told apart on the board, so one of the groups
+    /// could never be matched
+    DuplicateWord,
+    /// A red herring for a word or group that isn't there, or for the word's own group
+    Herring,
+    /// A label that's blank or the same as another group's, so the groups can't be told apart,
+    /// or that's over the [`super::limits`]
+    Label,
+    /// A display color that isn't `#rrggbb`
+    Hex,
+    /// A word or theme too long to decode, see [`super::limits`]
+    TooLong,
+    /// More hints than decode, or one that's too long
+    Hints,
+}
+
+/// Something wrong with one spot in a puzzle, so an editor can point at it
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Problem {
+    kind: ProblemKind,
+    /// `None` for problems with the puzzle as a whole
+    color: Option<Color>,
+    /// `None` for problems with a group as a whole
+    word_index: Option<usize>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Problem {
+    pub fn kind(&self) -> ProblemKind {
+        self.kind
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.color
+    }
+
+    pub fn word_index(&self) -> Option<usize> {
+        self.word_index
+    }
+
+    pub fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let color = self.color.map_or("", Color::name);
+        let word = self.word_index.map_or(0, |index| index + 1);
+        match self.kind {
+            ProblemKind::GroupCount => write!(
+                f,
+                "a puzzle needs between {MIN_GROUPS} and {MAX_GROUPS} groups"
+            ),
+            ProblemKind::GroupSize => write!(
+                f,
+                "the {color} group needs between {MIN_GROUP_SIZE} and {MAX_GROUP_SIZE} words"
+            ),
+            ProblemKind::Uneven => write!(
+                f,
+                "the {color} group has a different number of words than the first group"
+            ),
+            ProblemKind::EmptyTheme => write!(f, "the {color} group has no theme"),
+            ProblemKind::BlankWord => write!(f, "word {word} of the {color} group is blank"),
+            ProblemKind::DuplicateWord => {
+                write!(
+                    f,
+                    "word {word} of the {color} group is also in another group"
+                )
+            }
+            ProblemKind::Label => write!(
+                f,
+                "the {color} group's label is blank, over {MAX_TEXT_LEN} bytes or taken by another group"
+            ),
+            ProblemKind::Hex => write!(f, "the {color} group's color isn't a #rrggbb color"),
+            ProblemKind::TooLong => match self.word_index {
+                Some(_) => write!(
+                    f,
+                    "word {word} of the {color} group is over {MAX_TEXT_LEN} bytes"
+                ),
+                None => write!(f, "the {color} group's theme is over {MAX_TEXT_LEN} bytes"),
+            },
+            ProblemKind::Hints => write!(
+                f,
+                "the {color} group has more than {MAX_HINTS} hints, or one over {MAX_TEXT_LEN} bytes"
+            ),
+            ProblemKind::Herring => write!(
+                f,
+                "word {word} of the {color} group is a red herring for a group it can't be mistaken for"
+            ),
+        }
+    }
+}
+
+/// Every problem found with a puzzle, in group and then word order
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    problems: Vec<Problem>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ValidationError {
+    pub fn problems(&self) -> Vec<Problem> {
+        self.problems.clone()
+    }
+
+    pub fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problems: Vec<String> = self.problems.iter().map(Problem::to_string).collect();
+        write!(f, "{}", problems.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl ValidationError {
+    pub fn problems_ref(&self) -> &[Problem] {
+        &self.problems
+    }
+}
+
+/// Checks everything about `sets` at once rather than stopping at the first problem.
+/// Groups past the last color can't be told apart, so only their count is a problem.
+pub(super) fn validate(sets: &[ConnectionSet]) -> Result<(), ValidationError> {
+    let mut problems = Vec::new();
+    let mut report = |kind, color, word_index| {
+        problems.push(Problem {
+            kind,
+            color,
+            word_index,
+        })
+    };
+
+    if !(MIN_GROUPS..=MAX_GROUPS).contains(&sets.len()) {
+        report(ProblemKind::GroupCount, None, None);
+    }
+    let group_size = sets.first().map_or(0, ConnectionSet::len);
+    let groups = sets.iter().take(MAX_GROUPS).enumerate();
+    for (index, set) in groups.clone() {
+        let color = Color::from_int(index as u8);
+        if !(MIN_GROUP_SIZE..=MAX_GROUP_SIZE).contains(&set.len()) {
+            report(ProblemKind::GroupSize, color, None);
+        } else if set.len() != group_size {
+            report(ProblemKind::Uneven, color, None);
+        }
+        if set.theme_ref().trim().is_empty() {
+            report(ProblemKind::EmptyTheme, color, None);
+        } else if set.theme_ref().len() > MAX_TEXT_LEN {
+            report(ProblemKind::TooLong, color, None);
+        }
+        if set.hints().len() > MAX_HINTS || set.hints().iter().any(|h| h.len() > MAX_TEXT_LEN) {
+            report(ProblemKind::Hints, color, None);
+        }
+        let label = |index: usize, set: &ConnectionSet| {
+            set.custom_label()
+                .or(Color::from_int(index as u8).map(Color::name))
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase()
+        };
+        if label(index, set).is_empty()
+            || set
+                .custom_label()
+                .is_some_and(|label| label.len() > MAX_TEXT_LEN)
+            || groups.clone().any(|(other, other_set)| {
+                other != index && label(other, other_set) == label(index, set)
+            })
+        {
+            report(ProblemKind::Label, color, None);
+        }
+        if set.custom_hex().is_some_and(|hex| !is_hex(hex)) {
+            report(ProblemKind::Hex, color, None);
+        }
+
+        for (word_index, word) in set.words_list_ref().enumerate() {
+            if word.trim().is_empty() {
+                report(ProblemKind::BlankWord, color, Some(word_index));
+            } else if word.len() > MAX_TEXT_LEN {
+                report(ProblemKind::TooLong, color, Some(word_index));
+            } else if groups
+                .clone()
+                .any(|(other, set)| other != index && set.words_list_ref().any(|w| same(w, word)))
+            {
+                report(ProblemKind::DuplicateWord, color, Some(word_index));
+            }
+        }
+        // no more herrings than words decode
+        for (number, herring) in set.herrings().iter().enumerate() {
+            let decoy = herring.decoy.index();
+            if number >= MAX_GROUP_SIZE
+                || herring.word_index >= set.len()
+                || decoy >= sets.len()
+                || decoy == index
+            {
+                report(ProblemKind::Herring, color, Some(herring.word_index));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { problems })
+    }
+}
+
+fn is_hex(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|digits| digits.len() == 6 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Whether two words would look the same on the board
+fn same(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sets: &[ConnectionSet]) -> Vec<(ProblemKind, Option<Color>, Option<usize>)> {
+        validate(sets)
+            .unwrap_err()
+            .problems
+            .iter()
+            .map(|problem| (problem.kind, problem.color, problem.word_index))
+            .collect()
+    }
+
+    fn sets() -> Vec<ConnectionSet> {
+        vec![
+            ConnectionSet::new("Fish", &["bass", "pike", "carp", "sole"]),
+            ConnectionSet::new("Trees", &["oak", "elm", "ash", "pine"]),
+        ]
+    }
+
+    #[test]
+    fn a_good_puzzle_passes() {
+        assert_eq!(validate(&sets()), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_problem_in_order() {
+        let sets = [
+            ConnectionSet::new(" ", &["bass", "", "carp", "sole"]),
+            ConnectionSet::new("Trees", &["oak", "elm", "ash", " Bass"]),
+            ConnectionSet::new("Keys", &["shift", "tab"]),
+        ];
+        assert_eq!(
+            kinds(&sets),
+            [
+                (ProblemKind::EmptyTheme, Some(Color::Yellow), None),
+                (ProblemKind::DuplicateWord, Some(Color::Yellow), Some(0)),
+                (ProblemKind::BlankWord, Some(Color::Yellow), Some(1)),
+                (ProblemKind::DuplicateWord, Some(Color::Blue), Some(3)),
+                (ProblemKind::Uneven, Some(Color::Purple), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_groups_and_words() {
+        assert_eq!(kinds(&sets()[..1]), [(ProblemKind::GroupCount, None, None)]);
+        let sets = [
+            ConnectionSet::new("One", &["a"]),
+            ConnectionSet::new("Other", &["b"]),
+        ];
+        assert_eq!(
+            kinds(&sets),
+            [
+                (ProblemKind::GroupSize, Some(Color::Yellow), None),
+                (ProblemKind::GroupSize, Some(Color::Blue), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn styles_have_to_tell_groups_apart() {
+        let mut sets = sets();
+        sets[0] = sets[0].clone().with_label("blue").with_hex("red");
+        sets[1] = sets[1].clone().with_herring(0, Color::Blue);
+        assert_eq!(
+            kinds(&sets),
+            [
+                (ProblemKind::Label, Some(Color::Yellow), None),
+                (ProblemKind::Hex, Some(Color::Yellow), None),
+                (ProblemKind::Label, Some(Color::Blue), None),
+                (ProblemKind::Herring, Some(Color::Blue), Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn messages_name_the_spot() {
+        let sets = [
+            ConnectionSet::new("Fish", &["bass", "", "carp", "sole"]),
+            ConnectionSet::new("Trees", &["oak", "elm", "ash", "pine"]),
+        ];
+        assert_eq!(
+            validate(&sets).unwrap_err().to_string(),
+            "word 2 of the yellow group is blank"
+        );
+    }
+}
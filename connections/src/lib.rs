@@ -1,13 +1,38 @@
 mod game;
-use wasm_bindgen::prelude::*;
+pub use game::alternative::{Alternative, CardMove};
+pub use game::archive::{ArchiveError, DailyPuzzle, PuzzleArchive};
+pub use game::board::{Card, CardState, Selection, SelectionFailiure};
+pub use game::color::{Color, ColorIter};
+pub use game::dictionary;
+pub use game::envelope::{
+    self, CodeVersion, CURRENT_VERSION, LATEST_VERSION, LOCKED_VERSION, PACK_VERSION,
+    SEALED_VERSION,
+};
+pub use game::herring::{RedHerring, Trap};
+pub use game::hints::{Hint, HintCost, HintError, HintKind, UsedHint, MAX_SCORE};
+pub use game::limits;
+pub use game::log::{GuessLog, GuessOutcome, LoggedGuess};
+pub use game::metadata::Metadata;
+pub use game::pack::{PackError, PuzzlePack};
+pub use game::puzzle::{ConnectionPuzzle, ConnectionSet, PuzzleRef, TranscodingError};
+pub use game::save::{SaveError, SaveState};
+pub use game::signing::{self, KeyError, Keyring};
+pub use game::validate::{Problem, ProblemKind, ValidationError};
+pub use game::{
+    seed_from_text, Failiure, GameState, Rules, SelectionSuccess, SelectionSuccessTags,
+};
 
-//use web_sys::console;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(main)]
 fn main() {
     console_error_panic_hook::set_once();
 }
 
+#[cfg(feature = "wasm")]
+#[allow(unused_macros)]
 macro_rules! console_log {
     ($expr:expr) => (web_sys::console::log_1(&(AsRef::<str>::as_ref($expr)).into()));
     ($($y:expr),+) => (
@@ -17,4 +42,6 @@ macro_rules! console_log {
     );
 }
 
+#[cfg(feature = "wasm")]
+#[allow(unused_imports)]
 pub(crate) use console_log;
@@ -1,4 +1,4 @@
-import init, {GameState, Failiure, JsSelectionSuccess, CardState, SelectionSuccessTags} from './pkg/nyt_connections.js';
+import init, {GameState, ConnectionPuzzle, Color, Failiure, JsSelectionSuccess, CardState, SelectionSuccessTags, seed_from_text} from './pkg/nyt_connections.js';
 import {Button} from './index.js';
 
 let elems = null;
@@ -24,7 +24,12 @@ function init_board(parent){
 }
 
 function init_elems(puzzle){
-    const game = puzzle ? GameState.new(puzzle) : GameState.new();
+    // a seed= link parameter gives everyone opening the link the same shuffles
+    const seed = new URL(document.URL).searchParams.get("seed");
+    puzzle = puzzle ?? ConnectionPuzzle.decode("default");
+    const game = seed == null
+        ? GameState.new(puzzle)
+        : GameState.seeded(puzzle, undefined, seed_from_text(seed));
     elems = {game:game};
     elems = {
         game:game,
@@ -32,22 +37,57 @@ function init_elems(puzzle){
         shuffle: new Button("shuffle", shuffle),
         deselect: new Button("deselect", deselect),
         submit: new Button("submit", submit),
-        remaining: new RemainingTries(),
+        remaining: new RemainingTries(game.mistakes_remaining()),
         one_away: document.getElementById("away"),
         already_guessed: document.getElementById("already"),
         end_screen: end_screen(),
     };
     elems.selection = new Selection(elems.board);
     elems.shuffle.enable();
+    show_metadata(game.metadata());
+    show_verified(game.verified_by());
+}
+
+function show_verified(signer){
+    if (signer){
+        const verified = document.getElementById("verified");
+        verified.textContent = `✔ Verified: signed by ${signer}`;
+        verified.classList.remove("hidden");
+    }
+}
+
+function show_metadata(metadata){
+    const title = document.getElementById("puzzle-title");
+    if (metadata.title()){
+        title.textContent = metadata.author()
+            ? `${metadata.title()} by ${metadata.author()}`
+            : metadata.title();
+        title.classList.remove("hidden");
+    }
+    if (metadata.intro()){
+        document.getElementById("intro").textContent = metadata.intro();
+    }
+    const end_note = document.getElementById("end-note");
+    if (metadata.end_note()){
+        end_note.textContent = metadata.end_note();
+        end_note.classList.remove("hidden");
+    }
 }
 
 function render_card(card){
     card.textContent = elems.game.card_text(card.index);
 }
 
+// puzzles can have any number of groups of any size, so the board is laid out for the one
+// being played instead of the page's
 class Board{
     constructor(){
         this.board = document.getElementById('board');
+        const rows = elems.game.num_groups();
+        this.board.style.gridTemplateColumns = `repeat(${group_size()}, 1fr)`;
+        this.board.style.gridTemplateRows = `repeat(${rows}, 1fr)`;
+        this.board.style.height = `calc(${rows - 1} * 8px + ${rows} * 80px)`;
+        this.board.replaceChildren(...new_cards());
         this.cards_list = board.querySelectorAll('connections-card');
         this.forEach(render_card);
         this.start_offset = 0;
@@ -55,7 +95,7 @@ class Board{
 
     async move_selection(){
         const selection_indices = Array.from(elems.selection.all).map((card) => (card.index));
-        const top_row_indices = Array.from(this.cards_list).slice(0, 4).map((card) => (card.index));
+        const top_row_indices = Array.from(this.cards_list).slice(0, group_size()).map((card) => (card.index));
 
         const cards_i = selection_indices.map((card, index) => {
             return ({selection:card, top:top_row_indices[index]});
@@ -73,7 +113,8 @@ class Board{
         });
 
         
-        for(const {selection, top} of cards_i.slice(0,3)){
+        const last = cards_i.pop();
+        for(const {selection, top} of cards_i){
             this.update();
             const s = this.at(selection); 
             const d = this.at(top); 
@@ -84,18 +125,18 @@ class Board{
             }
         }
         this.update();
-        const s = this.at(cards_i[3].selection); 
-        const t = this.at(cards_i[3].top); 
+        const s = this.at(last.selection); 
+        const t = this.at(last.top); 
         await this.swap_move(s,t);
         if(s.index >= this.rowEnd){
             s.disabled = false;
         }
-        this.start_offset+=4;
+        this.start_offset+=group_size();
         this.update();
     }
 
     get rowEnd(){
-        this.start_offset + 4;
+        this.start_offset + group_size();
     }
 
     at(index){
@@ -111,8 +152,8 @@ class Board{
     }
 
     add_set(set){
-        const last = this.cards_list[3];
-        const slice = Array.from(this.cards_list).slice(0,3);
+        const last = this.cards_list[group_size() - 1];
+        const slice = Array.from(this.cards_list).slice(0, group_size() - 1);
         for(const card of slice){
             card.remove();
         }
@@ -172,11 +213,7 @@ class Board{
     }
 
     reset(){ 
-        let new_cards = [new_card(0), new_card(1), new_card(2), new_card(3), 
-            new_card(4), new_card(5), new_card(6), new_card(7), new_card(8),
-            new_card(9), new_card(10), new_card(11), new_card(12), new_card(13),
-            new_card(14), new_card(15)]
-        this.board.replaceChildren(...new_cards);
+        this.board.replaceChildren(...new_cards());
         this.update();
         this.update_text();
         elems.selection.update();
@@ -225,11 +262,12 @@ class Selection{
 
 
     async jump(){
-        for(let i = 0; i< 3; i++){
+        const last = this.arr.length - 1;
+        for(let i = 0; i< last; i++){
             this.arr[i].animate(Selection.jump, 450 );
             await new Promise(r => setTimeout(r, 100));
         }
-        await this.arr[3].animate(Selection.jump, 450 ).finished;
+        await this.arr[last].animate(Selection.jump, 450 ).finished;
 
     }
 
@@ -270,6 +308,9 @@ async function submit(){
             pop_up(elems.already_guessed);
             return;
         }
+        if(e == Failiure.GameOver){
+            return;
+        }
         elems.remaining.lose_one();
         switch(e){
             case Failiure.Mismatch:
@@ -340,10 +381,10 @@ function update_buttons(){
 }
 
 function update_submit(len){
-    if(len == 4){
+    if(len == group_size()){
         elems.submit.enable();
     }
-    else if(len == 3){
+    else if(len == group_size() - 1){
         elems.submit.disable();
     }
 }
@@ -360,9 +401,18 @@ function update_deselect(len){
 function new_card(i){
     const elem = document.createElement("connections-card");
     elem.setAttribute("i", i);
+    elem.setAttribute("tabindex", 0);
     return(elem);
 }
 
+function new_cards(){
+    return(Array.from({length: elems.game.num_cards()}, (_, i) => new_card(i)));
+}
+
+function group_size(){
+    return(elems.game.group_size());
+}
+
 
 class Card extends HTMLElement{
     static observedAttributes = ["disabled"];
@@ -402,11 +452,11 @@ class Card extends HTMLElement{
     }
 
     get row(){
-        return(Math.floor( this.index / 4) + 1);
+        return(Math.floor( this.index / group_size()) + 1);
     }
 
     get column(){
-        return(Math.floor( this.index % 4) + 1);
+        return(Math.floor( this.index % group_size()) + 1);
     }
 
     get disabled(){
@@ -431,7 +481,7 @@ class Card extends HTMLElement{
     }
 
     on_click(){
-        if(this.selected || elems.selection.len < 4){
+        if(this.selected || elems.selection.len < group_size()){
             this.toggle_select();
         }
     }
@@ -474,13 +524,29 @@ class Card extends HTMLElement{
 
 
 class RemainingTries{
-    constructor(){
+    constructor(limit){
+        const container = document.getElementsByClassName('remaining_tries').item(0);
         this.handle = document.getElementsByClassName('dot');
         this.hidden = document.getElementsByClassName('dot hidden');
+        if (limit === undefined){
+            container.classList.add('hidden');
+            return;
+        }
+        while (this.handle.length > limit){
+            this.handle.item(this.handle.length - 1).remove();
+        }
+        while (this.handle.length < limit){
+            const dot = document.createElement("span");
+            dot.classList.add('dot');
+            container.appendChild(dot);
+        }
     }
 
     lose_one(){
         const last_ind = this.handle.length - 1 - this.hidden.length ;
+        if (last_ind < 0){
+            return;
+        }
         this.handle.item(last_ind).classList.add('hidden');
     }
 
@@ -495,6 +561,10 @@ class RemainingTries{
 
 function show_end_screen(e, won){
     end_buttons(e).forEach((button) => {button.enable()});
+    // a locked puzzle's answers would be in the edit link for anyone to read
+    if(elems.game.is_locked()){
+        e.edit.disable();
+    }
     if(won){
         e.win.classList.add("enabled")
     }else{
@@ -556,23 +626,15 @@ function end_screen(){
 		// disable all the buttons except the back button
 	}
 
+	// the result ends with the link the game was opened from, so it can be played from there
 	async function share(){
-		const code = elems.game.puzzle_code();
 		const url = new URL(document.URL);
 		url.searchParams.delete("edit");
-		url.searchParams.set("game", code);
-		await navigator.clipboard.writeText(url.href);
+		if (!url.searchParams.has("game")){
+			url.searchParams.set("game", elems.game.puzzle_code());
+		}
+		await elems.game.copy_result(undefined, url.href);
 		await copied_link();
-
-		/*
-			const url = new URL("game.html",Dom.url);
-		url.searchParams.set("game", Dom.puzzle.encode());
-		// Copy the text inside the text field
-		await navigator.clipboard.writeText(url.href);
-		await copied_link(Dom);
-		*/
-			// copy link to clipboard
-		// animte "copied link"
 	}
 
 	async function copied_link(){
@@ -586,6 +648,9 @@ function end_screen(){
 	}
 
 	function edit(){
+		if (elems.game.is_locked()){
+			return;
+		}
 		const code = elems.game.puzzle_code();
 		const url = new URL(document.URL);
 		url.searchParams.set("edit", code);
@@ -627,18 +692,25 @@ export class ConnectionSet extends HTMLElement{
 		shadowRoot.appendChild(templateContent.cloneNode(true));
 	}
 
-	attributeChangedCallback(name, _oldValue, newValue) {
-		if (name != "connections-color"){
+	// the attribute is the color's name for the stylesheet, and the group goes by `this.color`
+	attributeChangedCallback(name, _oldValue, _newValue) {
+		if (name != "connections-color" || this.color === undefined){
+			return;
+		}
+		const words_n_theme = elems.game.matched_text(this.color);
+		if (words_n_theme === undefined){
 			return;
 		}
-		const words_n_theme = elems.game.matched_text(newValue);
 		this.theme = words_n_theme[0];
 		this.words =  words_n_theme[1];
+		// puzzles can color their groups however they like, so this wins over the stylesheet
+		this.style.backgroundColor = elems.game.group_hex(this.color) ?? "";
 	}
 
 	update_color(color) {
-		if (color) {
-			this.setAttribute("connections-color", color);
+		this.color = color;
+		if (color !== undefined) {
+			this.setAttribute("connections-color", Color[color].toLowerCase());
 		} else {
 			this.removeAttribute("connections-color");
 		}
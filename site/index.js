@@ -1,4 +1,4 @@
-import init, {ConnectionPuzzle} from './pkg/nyt_connections.js';
+import init, {ConnectionPuzzle, Keyring, PuzzlePack} from './pkg/nyt_connections.js';
 import {start_game} from './game.js';
 
 async function load_asm(){
@@ -45,6 +45,10 @@ class InputSet{
     }
 
     set_text(connection_set){
+        // the editor only has four groups, and a puzzle can have fewer
+        if (!connection_set){
+            return;
+        }
         this.theme.value = connection_set.theme();
 
         const words = connection_set.words_list();
@@ -71,6 +75,20 @@ class InputSet{
         this.words[3].addEventListener("input", (elem) => {update_elem(elem, start+4)}, {passive:true});
     }
 
+    clear_problems(){
+        [this.theme, ...this.words].forEach((input) => {
+            input.setCustomValidity("");
+            input.title = "";
+        });
+    }
+
+    show_problem(problem){
+        const index = problem.word_index();
+        const input = index === undefined ? this.theme : this.words[index];
+        input.setCustomValidity(problem.message());
+        input.title = problem.message();
+    }
+
     full(){
         return this.theme.full && this.words.every((elem) => {elem.full})
     }
@@ -100,6 +118,24 @@ class Inputs{
         this.blue.set_text(connection_puzzle.blue_owned());
     }
 
+    by_color(color){
+        return [this.yellow, this.blue, this.purple, this.green][color];
+    }
+
+    clear_problems(){
+        [this.yellow, this.blue, this.purple, this.green].forEach((set) => set.clear_problems());
+    }
+
+    // marks every input the puzzle has a problem with, so it can be fixed in place
+    show_problems(error){
+        error.problems().forEach((problem) => {
+            const set = this.by_color(problem.color());
+            if (set !== undefined){
+                set.show_problem(problem);
+            }
+        });
+    }
+
     set_callbacks(){
         this.yellow.set_callbacks(0);
         this.green.set_callbacks(5);
@@ -142,6 +178,44 @@ function check_full(){
 }
 
 
+// [name, public key] for everyone whose signed codes count as official, from `codec keygen`
+const TRUSTED_KEYS = [];
+
+// locked codes are for quizzes, so the passphrase is asked for rather than put in the link
+function decode_game(code){
+    const keyring = TRUSTED_KEYS.reduce(
+        (keyring, [name, key]) => keyring.trust(name, key),
+        new Keyring(),
+    );
+    // a pack link picks its puzzle with puzzle=, numbered from 1
+    if (PuzzlePack.is_pack(code)){
+        const number = Number(Dom.url.searchParams.get("puzzle") ?? 1);
+        return PuzzlePack.decode_trusted(code, keyring).puzzle_number(number);
+    }
+    if (!ConnectionPuzzle.is_locked(code)){
+        return ConnectionPuzzle.decode_trusted(code, keyring);
+    }
+    const passphrase = window.prompt("This puzzle is locked. What's the passphrase?");
+    return ConnectionPuzzle.decode_locked_trusted(code, passphrase ?? "", keyring);
+}
+
+// a link that doesn't open says why instead of just showing the editor
+function show_code_error(error){
+    const messages = {
+        Truncated: "This link was cut short. Try copying the whole thing.",
+        Typo: `This link has a typo at character ${error.position}.`,
+        Missing: `This link is missing a character at ${error.position}.`,
+        Extra: `This link has an extra character at ${error.position}.`,
+        Mistyped: "This link has a few typos. Try copying it again.",
+        NotACode: "This link doesn't have a puzzle in it.",
+        Corrupt: "This puzzle is damaged and can't be played.",
+        TooLarge: "This link is too big to be a puzzle.",
+    };
+    const element = document.getElementById("code-error");
+    element.textContent = messages[error.name] ?? error.message;
+    element.classList.remove("hidden");
+}
+
 function start_editor(){
     const game = game_code();
     const edit = edit_code();
@@ -149,10 +223,11 @@ function start_editor(){
 
     if (game != null){
         try{
-            const puzzle = ConnectionPuzzle.decode(game);
+            const puzzle = decode_game(game);
             start_game(Dom.game_div, puzzle);
             return;
-        }catch{
+        }catch (error){
+            show_code_error(error);
         }
     }
     
@@ -222,7 +297,15 @@ function get_puzzle(){
 }
 
 function enable_game(){
-    const puzzle = get_puzzle();
+    Dom.inputs.clear_problems();
+    let puzzle;
+    try{
+        puzzle = get_puzzle();
+    }catch (error){
+        Dom.inputs.show_problems(error);
+        disable_game();
+        return;
+    }
     Dom.puzzle = puzzle;
     Dom.try_game.enable();
     Dom.copy_link.enable();